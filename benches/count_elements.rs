@@ -0,0 +1,52 @@
+//! Compares [`xtoken::count_elements`] to counting the elements of a full tokenization
+//!
+//! Run with `cargo bench --bench count_elements`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use xtoken::{count_elements, Token, Tokenizer};
+
+const ITERATIONS: u32 = 200;
+
+fn sample() -> Vec<u8> {
+    let mut doc = b"<?xml version='1.0'?>\n<!-- generated -->\n<root>\n".to_vec();
+    for i in 0..5000 {
+        let record = format!(
+            "  <record id=\"{i}\" kind='test'>\n    <name>Record &amp; {i}</name>\n    <flag/>\n  </record>\n"
+        );
+        doc.extend_from_slice(record.as_bytes());
+    }
+    doc.extend_from_slice(b"</root>\n");
+    doc
+}
+
+fn bench(name: &str, mut f: impl FnMut() -> usize) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(f());
+    }
+    let elapsed = start.elapsed() / ITERATIONS;
+    println!("{name:>16}: {elapsed:?}/iter");
+    elapsed
+}
+
+fn main() {
+    let input = sample();
+    let expected = Tokenizer::new(&input)
+        .filter(|t| matches!(t, Token::Element(_) | Token::EmptyElement(_)))
+        .count();
+    assert_eq!(count_elements(&input), expected);
+
+    let fast = bench("count_elements", || count_elements(black_box(&input)));
+    let slow = bench("tokenizer", || {
+        Tokenizer::new(black_box(&input))
+            .filter(|t| matches!(t, Token::Element(_) | Token::EmptyElement(_)))
+            .count()
+    });
+    println!(
+        "{:>16}: {:.1}x",
+        "speedup",
+        slow.as_secs_f64() / fast.as_secs_f64()
+    );
+}
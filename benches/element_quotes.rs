@@ -0,0 +1,113 @@
+//! Measures the cost of quote-aware element scanning
+//!
+//! Compares the [`Tokenizer`] on documents without, with few and with many
+//! quoted attributes. As a baseline, the end of each tag is also found with
+//! the quote-unaware `memchr` scan used before, with quote tracking but no
+//! fast path, and with the fast path the tokenizer uses.
+//! Run with `cargo bench --bench element_quotes`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use xtoken::Tokenizer;
+
+const ITERATIONS: u32 = 200;
+const RECORDS: usize = 5000;
+
+fn document(record: impl Fn(usize) -> String) -> Vec<u8> {
+    let mut doc = b"<root>\n".to_vec();
+    for i in 0..RECORDS {
+        doc.extend_from_slice(record(i).as_bytes());
+    }
+    doc.extend_from_slice(b"</root>\n");
+    doc
+}
+
+/// The end of a tag before quote-awareness, which stops at a quoted `>`
+fn tag_end_unquoted(bytes: &[u8]) -> Option<usize> {
+    memchr::memchr(b'>', bytes)
+}
+
+/// Quote-aware end of a tag, checking every byte
+fn tag_end_slow(bytes: &[u8]) -> Option<usize> {
+    let mut quote = None;
+    for (pos, &b) in bytes.iter().enumerate() {
+        match (quote, b) {
+            (None, b'>') => return Some(pos),
+            (None, b'"' | b'\'') => quote = Some(b),
+            (Some(q), _) if q == b => quote = None,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Quote-aware end of a tag with a fast path for tags without quotes
+fn tag_end_fast(bytes: &[u8]) -> Option<usize> {
+    let mut pos = memchr::memchr3(b'>', b'"', b'\'', bytes)?;
+    while bytes[pos] != b'>' {
+        let quote = bytes[pos];
+        pos += 1;
+        pos += memchr::memchr(quote, &bytes[pos..])? + 1;
+        pos += memchr::memchr3(b'>', b'"', b'\'', &bytes[pos..])?;
+    }
+    Some(pos)
+}
+
+/// Find the end of every tag in `input` with `tag_end`
+fn scan_tags(input: &[u8], tag_end: fn(&[u8]) -> Option<usize>) -> usize {
+    let mut count = 0;
+    let mut rest = input;
+    while let Some(start) = memchr::memchr(b'<', rest) {
+        let Some(end) = tag_end(&rest[start..]) else {
+            break;
+        };
+        count += 1;
+        rest = &rest[(start + end + 1)..];
+    }
+    count
+}
+
+fn bench(name: &str, input: &[u8], mut f: impl FnMut(&[u8]) -> usize) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(f(black_box(input)));
+    }
+    let elapsed = start.elapsed() / ITERATIONS;
+    let throughput = input.len() as f64 / elapsed.as_secs_f64() / 1e6;
+    println!("{name:>20}: {elapsed:?}/iter ({throughput:.0} MB/s)");
+    elapsed
+}
+
+fn main() {
+    let plain = document(|i| format!("  <record>\n    <id>{i}</id>\n    <flag/>\n  </record>\n"));
+    let light = document(|i| format!("  <record id=\"{i}\">\n    <flag/>\n  </record>\n"));
+    let heavy = document(|i| {
+        format!(
+            "  <record id=\"{i}\" kind='test' title=\"a > b\" note='say \"hi\"' x=\"1\" y=\"2\"/>\n"
+        )
+    });
+    for (name, input) in [
+        ("no attributes", &plain),
+        ("attribute-light", &light),
+        ("attribute-heavy", &heavy),
+    ] {
+        println!("{name}");
+        assert_eq!(
+            scan_tags(input, tag_end_slow),
+            scan_tags(input, tag_end_fast)
+        );
+        let unquoted = bench("unquoted baseline", input, |b| {
+            scan_tags(b, tag_end_unquoted)
+        });
+        let slow = bench("quote-aware, slow", input, |b| scan_tags(b, tag_end_slow));
+        let fast = bench("quote-aware, fast", input, |b| scan_tags(b, tag_end_fast));
+        bench("tokenizer", input, |b| Tokenizer::new(b).count());
+        println!(
+            "{:>20}: {:.1}x vs. slow path, {:.1}x vs. unquoted",
+            "fast path",
+            slow.as_secs_f64() / fast.as_secs_f64(),
+            unquoted.as_secs_f64() / fast.as_secs_f64()
+        );
+    }
+}
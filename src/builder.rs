@@ -0,0 +1,148 @@
+//! Configuring a [`Tokenizer`] step by step
+
+use crate::{Tokenizer, TokenizerOptions};
+
+/// Builder for a [`Tokenizer`] with custom options
+///
+/// The default builder produces the same tokenizer as [`Tokenizer::new`].
+///
+/// ```
+/// use xtoken::{Token, TokenizerBuilder};
+///
+/// let mut tokens = TokenizerBuilder::new().entities(false).build(b"<p>{a&b}</p>");
+/// assert_eq!(tokens.nth(1), Some(Token::Span(b"{a&b}")));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TokenizerBuilder {
+    options: TokenizerOptions,
+}
+
+impl TokenizerBuilder {
+    /// Create a builder with the default options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Recognize entity references like `&amp;` (enabled by default)
+    ///
+    /// When disabled, `&` is ordinary text and never starts a `Token::Entity`
+    /// or an error, e.g. for templating languages that embed XML-like markup.
+    pub fn entities(mut self, enabled: bool) -> Self {
+        self.options.disable_entities = !enabled;
+        self
+    }
+
+    /// Recognize DTD internal subsets (enabled by default)
+    ///
+    /// When disabled, a `[` at the end of a declaration is part of the `Decl`
+    /// token and does not start a subset, so `]` is always ordinary text.
+    pub fn dtd_subsets(mut self, enabled: bool) -> Self {
+        self.options.disable_subsets = !enabled;
+        self
+    }
+
+    /// See [`Tokenizer::set_strict`]
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.options.strict = strict;
+        self
+    }
+
+    /// See [`Tokenizer::set_recover`]
+    pub fn recover(mut self, recover: bool) -> Self {
+        self.options.recover = recover;
+        self
+    }
+
+    /// See [`Tokenizer::set_reject_control_chars`]
+    pub fn reject_control_chars(mut self, reject: bool) -> Self {
+        self.options.reject_control_chars = reject;
+        self
+    }
+
+    /// See [`Tokenizer::set_allow_short_close`]
+    pub fn allow_short_close(mut self, allow: bool) -> Self {
+        self.options.allow_short_close = allow;
+        self
+    }
+
+    /// See [`Tokenizer::set_emit_eof`]
+    pub fn emit_eof(mut self, emit_eof: bool) -> Self {
+        self.options.emit_eof = emit_eof;
+        self
+    }
+
+    /// The options of the tokenizers that this builder produces
+    pub fn options(&self) -> TokenizerOptions {
+        self.options
+    }
+
+    /// Create a tokenizer for `bytes` with the configured options
+    pub fn build(self, bytes: &[u8]) -> Tokenizer<'_> {
+        Tokenizer::new(bytes).with_options(self.options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::TokenizerBuilder;
+    use crate::{Token, Tokenizer};
+    use std::vec::Vec;
+
+    #[test]
+    fn test_default_builder() {
+        let input = b"<!DOCTYPE a [<!ENTITY b 'c'>]><a>x &amp; y]]><b/>&</a>";
+        assert_eq!(
+            TokenizerBuilder::new().build(input).collect::<Vec<_>>(),
+            Tokenizer::new(input).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            TokenizerBuilder::new().build(input).options(),
+            Tokenizer::new(input).options()
+        );
+    }
+
+    #[test]
+    fn test_disable_entities() {
+        let mut t = Tokenizer::new(b"a&b");
+        assert_eq!(t.next(), Some(Token::Span(b"a")));
+        assert_eq!(t.next(), Some(Token::Error(b"&b")));
+
+        let builder = TokenizerBuilder::new().entities(false);
+        let tokens = builder.build(b"a&b").collect::<Vec<_>>();
+        assert_eq!(tokens, [Token::Span(b"a&b")]);
+        let tokens = builder
+            .build(b"<a x='&'>&amp;<b/>&</a>")
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                Token::Element(b"<a x='&'>"),
+                Token::Span(b"&amp;"),
+                Token::EmptyElement(b"<b/>"),
+                Token::Span(b"&"),
+                Token::ElementEnd(b"</a>"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_disable_subsets() {
+        let builder = TokenizerBuilder::new().dtd_subsets(false);
+        let tokens = builder
+            .build(b"<!DOCTYPE a [<!ENTITY b 'c'>]><a>]</a>")
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                Token::Decl(b"<!DOCTYPE a ["),
+                Token::Decl(b"<!ENTITY b 'c'>"),
+                Token::Span(b"]>"),
+                Token::Element(b"<a>"),
+                Token::Span(b"]"),
+                Token::ElementEnd(b"</a>"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,227 @@
+//! Serialization in a canonical form, loosely following Canonical XML 1.0
+
+use core::fmt::Write;
+
+use crate::entity::decode_reference;
+use crate::{pi, tag, DecodeError, Token, Tokenizer};
+
+/// Sort key of an attribute: namespace declarations first, then by qualified name
+fn attribute_key(name: &[u8]) -> (bool, &[u8]) {
+    let is_namespace = name == b"xmlns" || name.starts_with(b"xmlns:");
+    (!is_namespace, name)
+}
+
+fn write_utf8(w: &mut impl Write, bytes: &[u8]) -> Result<(), DecodeError> {
+    w.write_str(core::str::from_utf8(bytes).map_err(|_| DecodeError::Utf8)?)?;
+    Ok(())
+}
+
+/// Write `c` escaped for text content (`false`) or a double-quoted attribute value (`true`)
+fn write_escaped(w: &mut impl Write, c: char, attribute: bool) -> Result<(), DecodeError> {
+    match c {
+        '&' => w.write_str("&amp;")?,
+        '<' => w.write_str("&lt;")?,
+        '>' if !attribute => w.write_str("&gt;")?,
+        '"' if attribute => w.write_str("&quot;")?,
+        '\t' if attribute => w.write_str("&#x9;")?,
+        '\n' if attribute => w.write_str("&#xA;")?,
+        '\r' => w.write_str("&#xD;")?,
+        c => w.write_char(c)?,
+    }
+    Ok(())
+}
+
+/// Write literal bytes of text (`false`) or an attribute value (`true`), normalizing whitespace
+fn write_literal(w: &mut impl Write, bytes: &[u8], attribute: bool) -> Result<(), DecodeError> {
+    let text = core::str::from_utf8(bytes).map_err(|_| DecodeError::Utf8)?;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            // line ends are normalized to `\n`
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\r' | '\n' | '\t' if attribute => w.write_char(' ')?,
+            '\r' => w.write_char('\n')?,
+            c => write_escaped(w, c, attribute)?,
+        }
+    }
+    Ok(())
+}
+
+/// Write an undecoded attribute value, resolving its references
+fn write_attribute_value(w: &mut impl Write, mut value: &[u8]) -> Result<(), DecodeError> {
+    while let Some(pos) = memchr::memchr(b'&', value) {
+        write_literal(w, &value[..pos], true)?;
+        let end = memchr::memchr(b';', &value[pos..]).ok_or(DecodeError::Entity)? + pos + 1;
+        let c = decode_reference(&value[pos..end]).ok_or(DecodeError::Entity)?;
+        write_escaped(w, c, true)?;
+        value = &value[end..];
+    }
+    write_literal(w, value, true)
+}
+
+fn write_start_tag(w: &mut impl Write, span: &[u8]) -> Result<(), DecodeError> {
+    let element = tag::parse_element(span).ok_or(DecodeError::Malformed)?;
+    w.write_char('<')?;
+    write_utf8(w, element.name())?;
+    // selection sort, which needs no allocation; duplicate attributes are not well-formed
+    let mut last = None;
+    while let Some((name, value)) = element
+        .attributes()
+        .filter(|&(name, _)| last.is_none_or(|last| attribute_key(name) > last))
+        .min_by_key(|&(name, _)| attribute_key(name))
+    {
+        w.write_char(' ')?;
+        write_utf8(w, name)?;
+        w.write_str("=\"")?;
+        write_attribute_value(w, value)?;
+        w.write_char('"')?;
+        last = Some(attribute_key(name));
+    }
+    w.write_char('>')?;
+    Ok(())
+}
+
+fn write_end_tag(w: &mut impl Write, name: &[u8]) -> Result<(), DecodeError> {
+    w.write_str("</")?;
+    write_utf8(w, name)?;
+    w.write_char('>')?;
+    Ok(())
+}
+
+fn canonicalize(bytes: &[u8], w: &mut impl Write, comments: bool) -> Result<(), DecodeError> {
+    let mut tokens = Tokenizer::new(bytes);
+    let mut after_root = false;
+    while let Some(token) = tokens.next() {
+        let depth = tokens.depth();
+        // nodes outside of the root element are separated by a line feed
+        let outside = depth == 0 && !matches!(token, Token::ElementEnd(_));
+        match token {
+            Token::Span(span) if depth > 0 => write_literal(w, span, false)?,
+            Token::Entity(entity) if depth > 0 => {
+                let c = decode_reference(entity).ok_or(DecodeError::Entity)?;
+                write_escaped(w, c, false)?;
+            }
+            Token::CData(span) => write_literal(w, &span[9..(span.len() - 3)], false)?,
+            Token::Comment(span) if comments => {
+                if outside && after_root {
+                    w.write_char('\n')?;
+                }
+                write_utf8(w, span)?;
+                if outside && !after_root {
+                    w.write_char('\n')?;
+                }
+            }
+            Token::PI(span) => {
+                let (target, data) = pi::pi_target_data(span).ok_or(DecodeError::Malformed)?;
+                if target == b"xml" {
+                    continue;
+                }
+                if outside && after_root {
+                    w.write_char('\n')?;
+                }
+                w.write_str("<?")?;
+                write_utf8(w, target)?;
+                if !data.is_empty() {
+                    w.write_char(' ')?;
+                    write_utf8(w, data)?;
+                }
+                w.write_str("?>")?;
+                if outside && !after_root {
+                    w.write_char('\n')?;
+                }
+            }
+            Token::Element(span) => write_start_tag(w, span)?,
+            Token::EmptyElement(span) => {
+                write_start_tag(w, span)?;
+                write_end_tag(w, tag::element_name(span).ok_or(DecodeError::Malformed)?)?;
+                after_root |= depth == 0;
+            }
+            Token::ElementEnd(span) => {
+                write_end_tag(w, tag::element_name(span).ok_or(DecodeError::Malformed)?)?;
+                after_root |= depth == 0;
+            }
+            Token::Error(_) => return Err(DecodeError::Malformed),
+            // DTD, whitespace outside of the root element and skipped comments
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Write `bytes` in a canonical form, without comments
+///
+/// This is a simplified version of Canonical XML 1.0:
+///
+/// - The XML declaration, the DTD and a Byte Order Mark are removed
+/// - Line ends are normalized to `\n` and whitespace outside of the root
+///   element is replaced by a single `\n` between nodes
+/// - Empty element tags are written as a start and end tag
+/// - Attributes are written with double quotes and sorted, namespace
+///   declarations first
+/// - Character and predefined entity references are resolved, CDATA sections
+///   are replaced by their content and the result is escaped minimally
+///
+/// Unlike the specification, namespaces and attribute defaults from the DTD
+/// are not processed and other entities are rejected with [`DecodeError::Entity`].
+pub fn write_canonical(bytes: &[u8], w: &mut impl Write) -> Result<(), DecodeError> {
+    canonicalize(bytes, w, false)
+}
+
+/// Like [`write_canonical`], but keeps comments
+pub fn write_canonical_with_comments(bytes: &[u8], w: &mut impl Write) -> Result<(), DecodeError> {
+    canonicalize(bytes, w, true)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::{write_canonical, write_canonical_with_comments};
+    use crate::DecodeError;
+    use std::string::String;
+
+    fn canonical(bytes: &[u8]) -> Result<String, DecodeError> {
+        let mut out = String::new();
+        write_canonical(bytes, &mut out)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn test_canonical_tags() {
+        assert_eq!(
+            canonical(b"<?xml version='1.0'?>\n<doc><e1   /><e2 b = 'x' a=\"y\" xmlns:p='urn:p' ></e2 ></doc>\n")
+                .unwrap(),
+            "<doc><e1></e1><e2 xmlns:p=\"urn:p\" a=\"y\" b=\"x\"></e2></doc>"
+        );
+    }
+
+    #[test]
+    fn test_canonical_text() {
+        assert_eq!(
+            canonical(b"<a>x &amp; &#65;&gt;\r\n<![CDATA[<y> & \"z\"]]></a>").unwrap(),
+            "<a>x &amp; A&gt;\n&lt;y&gt; &amp; \"z\"</a>"
+        );
+        assert_eq!(
+            canonical(b"<a v=\"&quot;x&lt;&#9;\n'\"/>").unwrap(),
+            "<a v=\"&quot;x&lt;&#x9; '\"></a>"
+        );
+    }
+
+    #[test]
+    fn test_canonical_prolog() {
+        let input =
+            b"<!DOCTYPE a [<!ENTITY e 'x'>]>\n<!-- c -->\n<?pi   data?>\n<a/>\n<!-- d -->\n";
+        assert_eq!(canonical(input).unwrap(), "<?pi data?>\n<a></a>");
+        let mut out = String::new();
+        write_canonical_with_comments(input, &mut out).unwrap();
+        assert_eq!(out, "<!-- c -->\n<?pi data?>\n<a></a>\n<!-- d -->");
+    }
+
+    #[test]
+    fn test_canonical_errors() {
+        assert_eq!(canonical(b"<a>&e;</a>"), Err(DecodeError::Entity));
+        assert_eq!(canonical(b"<a x='&e;'/>"), Err(DecodeError::Entity));
+        assert_eq!(canonical(b"<a><b</a>"), Err(DecodeError::Malformed));
+        assert_eq!(canonical(b"<a>\xFF</a>"), Err(DecodeError::Utf8));
+    }
+}
@@ -0,0 +1,43 @@
+//! Parsing of comments (i.e. `<!-- ... -->`)
+
+use crate::tag::{is_space, trim_end, trim_start};
+
+/// Split a directive comment like `<!-- @generated by foo -->` into name and rest
+///
+/// A directive is a comment whose trimmed body starts with `@`. The name is
+/// returned without the `@` and the rest without surrounding whitespace.
+/// Returns `None` for all other comments.
+pub fn comment_directive(c: &[u8]) -> Option<(&[u8], &[u8])> {
+    let inner = c.strip_prefix(b"<!--")?;
+    let inner = inner
+        .strip_suffix(b"-->")
+        .or_else(|| inner.strip_suffix(b"--!>"))?;
+    let body = trim_start(inner).strip_prefix(b"@")?;
+    let len = body.iter().position(|&b| is_space(b)).unwrap_or(body.len());
+    if len == 0 {
+        return None;
+    }
+    let (name, rest) = body.split_at(len);
+    Some((name, trim_end(trim_start(rest))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::comment_directive;
+
+    #[test]
+    fn test_comment_directive() {
+        assert_eq!(
+            comment_directive(b"<!-- @generated by foo -->"),
+            Some((&b"generated"[..], &b"by foo"[..]))
+        );
+        assert_eq!(
+            comment_directive(b"<!--@deprecated-->"),
+            Some((&b"deprecated"[..], &b""[..]))
+        );
+        assert_eq!(comment_directive(b"<!-- generated by foo -->"), None);
+        assert_eq!(comment_directive(b"<!-- a @b -->"), None);
+        assert_eq!(comment_directive(b"<!-- @ -->"), None);
+        assert_eq!(comment_directive(b"<?pi @x?>"), None);
+    }
+}
@@ -0,0 +1,112 @@
+//! Helpers concerning the structure of a whole document
+
+use crate::{Token, Tokenizer};
+
+/// Split a document into its prolog, root element and epilog
+///
+/// The root element spans from the start tag of the first element to its
+/// matching end tag (or is just that tag, if it is self-closing). If the
+/// root element is never closed, it extends to the end of the input and if
+/// there is no root element at all, everything is considered prolog.
+pub fn split_document(bytes: &[u8]) -> (&[u8], &[u8], &[u8]) {
+    let mut tokens = Tokenizer::new(bytes);
+    let mut start = None;
+    let mut depth = 0usize;
+    loop {
+        let offset = bytes.len() - tokens.remaining().len();
+        let closed = match tokens.next() {
+            None => break,
+            Some(Token::Element(_)) => {
+                start.get_or_insert(offset);
+                depth += 1;
+                false
+            }
+            Some(Token::EmptyElement(_)) => {
+                start.get_or_insert(offset);
+                depth == 0
+            }
+            Some(Token::ElementEnd(_)) if start.is_some() => {
+                depth -= 1;
+                depth == 0
+            }
+            Some(_) => false,
+        };
+        if let (true, Some(start)) = (closed, start) {
+            let end = bytes.len() - tokens.remaining().len();
+            let (prolog, rest) = bytes.split_at(start);
+            let (root, epilog) = rest.split_at(end - start);
+            return (prolog, root, epilog);
+        }
+    }
+    let (prolog, root) = bytes.split_at(start.unwrap_or(bytes.len()));
+    (prolog, root, &root[root.len()..])
+}
+
+/// Iterator over the byte offsets of all start and empty element tags named `name`
+pub fn find_all_elements<'a>(bytes: &'a [u8], name: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+    let mut tokens = Tokenizer::new(bytes);
+    core::iter::from_fn(move || loop {
+        let offset = bytes.len() - tokens.remaining().len();
+        if let Token::Element(span) | Token::EmptyElement(span) = tokens.next()? {
+            if crate::tag::element_name(span) == Some(name) {
+                return Some(offset);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::{find_all_elements, split_document};
+    use std::vec::Vec;
+
+    #[test]
+    fn test_find_all_elements() {
+        let doc =
+            b"<records><record id='1'/><records/><record>x</record>\n<record ></record></records>";
+        let offsets = find_all_elements(doc, b"record").collect::<Vec<_>>();
+        assert_eq!(offsets, [9, 35, 54]);
+        assert_eq!(&doc[35..43], b"<record>");
+        assert_eq!(find_all_elements(doc, b"missing").count(), 0);
+    }
+
+    #[test]
+    fn test_split_document() {
+        let doc = b"<?xml version='1.0'?>\n<!DOCTYPE a>\n<a><a/><b></b>x</a>\n<!-- end -->\n";
+        assert_eq!(
+            split_document(doc),
+            (
+                &b"<?xml version='1.0'?>\n<!DOCTYPE a>\n"[..],
+                &b"<a><a/><b></b>x</a>"[..],
+                &b"\n<!-- end -->\n"[..]
+            )
+        );
+    }
+
+    #[test]
+    fn test_split_document_self_closing() {
+        assert_eq!(
+            split_document(b"<!-- x --><root/><!-- y -->"),
+            (&b"<!-- x -->"[..], &b"<root/>"[..], &b"<!-- y -->"[..])
+        );
+    }
+
+    #[test]
+    fn test_split_document_without_root() {
+        assert_eq!(
+            split_document(b"<?pi?> text"),
+            (&b"<?pi?> text"[..], &b""[..], &b""[..])
+        );
+        assert_eq!(split_document(b""), (&b""[..], &b""[..], &b""[..]));
+    }
+
+    #[test]
+    fn test_split_document_unclosed_root() {
+        assert_eq!(
+            split_document(b"</x> <a><b></b>"),
+            (&b"</x> "[..], &b"<a><b></b>"[..], &b""[..])
+        );
+    }
+}
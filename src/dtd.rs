@@ -0,0 +1,401 @@
+//! Parsing of declarations (i.e. `Decl` tokens)
+
+use crate::tag::{is_space, trim_end, trim_start};
+
+/// Kind of a markup declaration, see [`Token::decl_kind`](crate::Token::decl_kind)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeclKind {
+    /// `<!DOCTYPE ...>`
+    Doctype,
+    /// `<!ELEMENT ...>`
+    Element,
+    /// `<!ATTLIST ...>`
+    Attlist,
+    /// `<!ENTITY ...>`
+    Entity,
+    /// `<!NOTATION ...>`
+    Notation,
+    /// Any other declaration
+    Other,
+}
+
+/// The keyword of a declaration, e.g. `DOCTYPE` for `<!DOCTYPE a>`
+fn keyword(decl: &[u8]) -> Option<&[u8]> {
+    let inner = decl.strip_prefix(b"<!")?;
+    let len = inner
+        .iter()
+        .position(|b| !b.is_ascii_alphabetic())
+        .unwrap_or(inner.len());
+    Some(&inner[..len])
+}
+
+/// Classify a declaration by its keyword
+pub(crate) fn decl_kind(decl: &[u8]) -> Option<DeclKind> {
+    Some(match keyword(decl)? {
+        b"DOCTYPE" => DeclKind::Doctype,
+        b"ELEMENT" => DeclKind::Element,
+        b"ATTLIST" => DeclKind::Attlist,
+        b"ENTITY" => DeclKind::Entity,
+        b"NOTATION" => DeclKind::Notation,
+        _ => DeclKind::Other,
+    })
+}
+
+/// Check whether a declaration is a doctype, optionally ignoring the case of `DOCTYPE`
+pub(crate) fn is_doctype(decl: &[u8], ignore_case: bool) -> bool {
+    keyword(decl).is_some_and(|keyword| {
+        if ignore_case {
+            keyword.eq_ignore_ascii_case(b"DOCTYPE")
+        } else {
+            keyword == b"DOCTYPE"
+        }
+    })
+}
+
+/// Content model of an element type, from its `<!ELEMENT ...>` declaration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContentModel {
+    /// `EMPTY`
+    Empty,
+    /// `ANY`
+    Any,
+    /// Text mixed with elements, e.g. `(#PCDATA|b)*`
+    Mixed,
+    /// Element-only content, e.g. `(a,b?)`
+    Children,
+}
+
+/// Split an element type declaration into the element name and its content model
+///
+/// Returns `None` for other declarations and if the content model is not one
+/// of `EMPTY`, `ANY` or a parenthesized expression.
+pub fn element_decl(decl: &[u8]) -> Option<(&[u8], ContentModel)> {
+    let inner = decl.strip_prefix(b"<!ELEMENT")?.strip_suffix(b">")?;
+    if !inner.first().copied().is_some_and(is_space) {
+        return None;
+    }
+    let inner = trim_start(inner);
+    let len = inner
+        .iter()
+        .position(|&b| is_space(b) || b == b'(')
+        .unwrap_or(inner.len());
+    let (name, spec) = inner.split_at(len);
+    let model = match trim_end(trim_start(spec)) {
+        b"EMPTY" => ContentModel::Empty,
+        b"ANY" => ContentModel::Any,
+        [b'(', group @ ..] if trim_start(group).starts_with(b"#PCDATA") => ContentModel::Mixed,
+        [b'(', ..] => ContentModel::Children,
+        _ => return None,
+    };
+    (!name.is_empty()).then_some((name, model))
+}
+
+/// An entity declaration (`<!ENTITY ...>`), see [`entity_decl`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntityDecl<'a> {
+    name: &'a [u8],
+    parameter: bool,
+    value: Option<&'a [u8]>,
+    public_id: Option<&'a [u8]>,
+    system_id: Option<&'a [u8]>,
+    notation: Option<&'a [u8]>,
+}
+
+impl<'a> EntityDecl<'a> {
+    /// The name of the entity
+    pub fn name(&self) -> &'a [u8] {
+        self.name
+    }
+
+    /// Whether this declares a parameter entity (`<!ENTITY % name ...>`)
+    pub fn is_parameter(&self) -> bool {
+        self.parameter
+    }
+
+    /// The literal value of an internal entity, without quotes
+    pub fn value(&self) -> Option<&'a [u8]> {
+        self.value
+    }
+
+    /// The public identifier of an external entity declared with `PUBLIC`
+    pub fn public_id(&self) -> Option<&'a [u8]> {
+        self.public_id
+    }
+
+    /// The system identifier (URI) of an external entity
+    pub fn system_id(&self) -> Option<&'a [u8]> {
+        self.system_id
+    }
+
+    /// The notation of an unparsed entity (`NDATA name`)
+    pub fn notation(&self) -> Option<&'a [u8]> {
+        self.notation
+    }
+
+    /// Whether this declares an unparsed entity, i.e. one with a notation
+    pub fn is_unparsed(&self) -> bool {
+        self.notation.is_some()
+    }
+}
+
+/// Split a quoted literal at the start of `bytes` into its content and the rest
+fn literal(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (&quote, rest) = bytes.split_first()?;
+    if !matches!(quote, b'"' | b'\'') {
+        return None;
+    }
+    let end = memchr::memchr(quote, rest)?;
+    Some((&rest[..end], &rest[(end + 1)..]))
+}
+
+/// Split a keyword or name at the start of `bytes` from the whitespace-trimmed rest
+fn word(bytes: &[u8]) -> (&[u8], &[u8]) {
+    let len = bytes
+        .iter()
+        .position(|&b| is_space(b))
+        .unwrap_or(bytes.len());
+    let (word, rest) = bytes.split_at(len);
+    (word, trim_start(rest))
+}
+
+/// Parse an entity declaration
+///
+/// Returns `None` for other declarations and malformed entity declarations.
+pub fn entity_decl(decl: &[u8]) -> Option<EntityDecl<'_>> {
+    let inner = decl.strip_prefix(b"<!ENTITY")?.strip_suffix(b">")?;
+    if !inner.first().copied().is_some_and(is_space) {
+        return None;
+    }
+    let (mut name, mut rest) = word(trim_start(inner));
+    let parameter = name == b"%";
+    if parameter {
+        (name, rest) = word(rest);
+    }
+    if name.is_empty() {
+        return None;
+    }
+    let mut entity = EntityDecl {
+        name,
+        parameter,
+        value: None,
+        public_id: None,
+        system_id: None,
+        notation: None,
+    };
+    let rest = match word(rest) {
+        (b"SYSTEM", rest) => {
+            let (system_id, rest) = literal(rest)?;
+            entity.system_id = Some(system_id);
+            rest
+        }
+        (b"PUBLIC", rest) => {
+            let (public_id, rest) = literal(rest)?;
+            let (system_id, rest) = literal(trim_start(rest))?;
+            entity.public_id = Some(public_id);
+            entity.system_id = Some(system_id);
+            rest
+        }
+        _ => {
+            let (value, rest) = literal(rest)?;
+            entity.value = Some(value);
+            rest
+        }
+    };
+    let rest = trim_start(rest);
+    if entity.system_id.is_some() && !parameter {
+        if let (b"NDATA", rest) = word(rest) {
+            let (notation, rest) = word(rest);
+            if notation.is_empty() || !rest.is_empty() {
+                return None;
+            }
+            entity.notation = Some(notation);
+            return Some(entity);
+        }
+    }
+    rest.is_empty().then_some(entity)
+}
+
+/// A document type declaration (`<!DOCTYPE ...>`), see [`Token::doctype`](crate::Token::doctype)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Doctype<'a> {
+    name: &'a [u8],
+    public_id: Option<&'a [u8]>,
+    system_id: Option<&'a [u8]>,
+    internal_subset: bool,
+}
+
+impl<'a> Doctype<'a> {
+    /// The name of the root element
+    pub fn name(&self) -> &'a [u8] {
+        self.name
+    }
+
+    /// The public identifier of the external subset declared with `PUBLIC`
+    pub fn public_id(&self) -> Option<&'a [u8]> {
+        self.public_id
+    }
+
+    /// The system identifier (URI) of the external subset
+    pub fn system_id(&self) -> Option<&'a [u8]> {
+        self.system_id
+    }
+
+    /// Whether an internal subset follows, i.e. the declaration is followed by a `SubsetStart`
+    pub fn has_internal_subset(&self) -> bool {
+        self.internal_subset
+    }
+}
+
+/// Parse a document type declaration, up to the internal subset if there is one
+///
+/// Returns `None` for other declarations and malformed document type declarations.
+pub(crate) fn doctype_decl(decl: &[u8]) -> Option<Doctype<'_>> {
+    let inner = decl.strip_prefix(b"<!DOCTYPE")?;
+    if !inner.first().copied().is_some_and(is_space) {
+        return None;
+    }
+    // without a `>`, the declaration is followed by a `SubsetStart`
+    let (inner, internal_subset) = match inner.strip_suffix(b">") {
+        Some(inner) => (inner, false),
+        None => (inner.strip_suffix(b"[").unwrap_or(inner), true),
+    };
+    let (name, rest) = word(trim_start(inner));
+    if name.is_empty() {
+        return None;
+    }
+    let mut doctype = Doctype {
+        name,
+        public_id: None,
+        system_id: None,
+        internal_subset,
+    };
+    let rest = match word(rest) {
+        (b"SYSTEM", rest) => {
+            let (system_id, rest) = literal(rest)?;
+            doctype.system_id = Some(system_id);
+            rest
+        }
+        (b"PUBLIC", rest) => {
+            let (public_id, rest) = literal(rest)?;
+            let (system_id, rest) = literal(trim_start(rest))?;
+            doctype.public_id = Some(public_id);
+            doctype.system_id = Some(system_id);
+            rest
+        }
+        _ => rest,
+    };
+    trim_start(rest).is_empty().then_some(doctype)
+}
+
+/// Length of the content of an `IGNORE` section, up to the matching `]]>`
+///
+/// Nested conditional sections are skipped, as their content is ignored too.
+pub(crate) fn ignore_section_len(bytes: &[u8]) -> Option<usize> {
+    let mut depth = 0;
+    let mut pos = 0;
+    loop {
+        pos += memchr::memchr2(b'<', b']', &bytes[pos..])?;
+        if bytes[pos..].starts_with(b"<![") {
+            depth += 1;
+            pos += 3;
+        } else if bytes[pos..].starts_with(b"]]>") {
+            if depth == 0 {
+                break Some(pos);
+            }
+            depth -= 1;
+            pos += 3;
+        } else {
+            pos += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decl_kind, doctype_decl, element_decl, entity_decl, ContentModel, DeclKind};
+
+    #[test]
+    fn test_doctype_decl() {
+        let d = doctype_decl(b"<!DOCTYPE html PUBLIC \"-//W3C//DTD XHTML 1.0//EN\" \"foo.dtd\" [")
+            .unwrap();
+        assert_eq!(d.name(), b"html");
+        assert_eq!(d.public_id(), Some(&b"-//W3C//DTD XHTML 1.0//EN"[..]));
+        assert_eq!(d.system_id(), Some(&b"foo.dtd"[..]));
+        assert!(d.has_internal_subset());
+
+        let d = doctype_decl(b"<!DOCTYPE doc SYSTEM 'a[b].dtd'>").unwrap();
+        assert_eq!(d.name(), b"doc");
+        assert_eq!(d.public_id(), None);
+        assert_eq!(d.system_id(), Some(&b"a[b].dtd"[..]));
+        assert!(!d.has_internal_subset());
+
+        let d = doctype_decl(b"<!DOCTYPE x [").unwrap();
+        assert_eq!((d.name(), d.system_id()), (&b"x"[..], None));
+        assert!(d.has_internal_subset());
+
+        assert_eq!(doctype_decl(b"<!DOCTYPE x PUBLIC 'p'>"), None);
+        assert_eq!(doctype_decl(b"<!DOCTYPE>"), None);
+        assert_eq!(doctype_decl(b"<!ELEMENT x ANY>"), None);
+        assert_eq!(doctype_decl(b"<!ATTLIST x a CDATA #IMPLIED>"), None);
+    }
+
+    #[test]
+    fn test_entity_decl_ndata() {
+        let e = entity_decl(b"<!ENTITY logo SYSTEM \"logo.gif\" NDATA gif>").unwrap();
+        assert_eq!(e.name(), b"logo");
+        assert_eq!(e.system_id(), Some(&b"logo.gif"[..]));
+        assert_eq!(e.notation(), Some(&b"gif"[..]));
+        assert!(e.is_unparsed());
+        assert!(!e.is_parameter());
+
+        let e = entity_decl(b"<!ENTITY doc PUBLIC '-//X//Y' 'doc.xml'>").unwrap();
+        assert_eq!(e.public_id(), Some(&b"-//X//Y"[..]));
+        assert_eq!(e.system_id(), Some(&b"doc.xml"[..]));
+        assert!(!e.is_unparsed());
+
+        assert_eq!(entity_decl(b"<!ENTITY % p SYSTEM 'p.ent' NDATA gif>"), None);
+        assert_eq!(entity_decl(b"<!ENTITY a 'b' NDATA gif>"), None);
+        assert_eq!(entity_decl(b"<!ENTITY logo SYSTEM 'logo.gif' NDATA>"), None);
+    }
+
+    #[test]
+    fn test_element_decl() {
+        assert_eq!(
+            element_decl(b"<!ELEMENT br EMPTY>"),
+            Some((&b"br"[..], ContentModel::Empty))
+        );
+        assert_eq!(
+            element_decl(b"<!ELEMENT x ANY >"),
+            Some((&b"x"[..], ContentModel::Any))
+        );
+        assert_eq!(
+            element_decl(b"<!ELEMENT p ( #PCDATA | b )*>"),
+            Some((&b"p"[..], ContentModel::Mixed))
+        );
+        assert_eq!(
+            element_decl(b"<!ELEMENT list(item+)>"),
+            Some((&b"list"[..], ContentModel::Children))
+        );
+        assert_eq!(element_decl(b"<!ELEMENT x %model;>"), None);
+        assert_eq!(element_decl(b"<!ELEMENTx EMPTY>"), None);
+        assert_eq!(element_decl(b"<!ATTLIST x a CDATA #IMPLIED>"), None);
+    }
+
+    #[test]
+    fn test_decl_kind() {
+        assert_eq!(decl_kind(b"<!DOCTYPE x>"), Some(DeclKind::Doctype));
+        assert_eq!(decl_kind(b"<!DOCTYPE x ["), Some(DeclKind::Doctype));
+        assert_eq!(decl_kind(b"<!ELEMENT x EMPTY>"), Some(DeclKind::Element));
+        assert_eq!(
+            decl_kind(b"<!ATTLIST x id ID #IMPLIED>"),
+            Some(DeclKind::Attlist)
+        );
+        assert_eq!(decl_kind(b"<!ENTITY % x 'y'>"), Some(DeclKind::Entity));
+        assert_eq!(
+            decl_kind(b"<!NOTATION gif SYSTEM 'x'>"),
+            Some(DeclKind::Notation)
+        );
+        assert_eq!(decl_kind(b"<!DOCTYPEX x>"), Some(DeclKind::Other));
+        assert_eq!(decl_kind(b"<x>"), None);
+    }
+}
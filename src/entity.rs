@@ -0,0 +1,160 @@
+//! Decoding of entity and character references
+
+use core::fmt;
+
+/// Error returned by [`Tokenizer::write_decoded_text`](crate::Tokenizer::write_decoded_text)
+/// and [`write_canonical`](crate::write_canonical)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The text is not valid UTF-8
+    Utf8,
+    /// An unknown, malformed or unterminated entity or character reference
+    Entity,
+    /// The writer returned an error
+    Fmt,
+    /// The input contains a malformed token
+    Malformed,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Utf8 => f.write_str("text is not valid UTF-8"),
+            DecodeError::Entity => f.write_str("invalid entity or character reference"),
+            DecodeError::Fmt => f.write_str("failed to write text"),
+            DecodeError::Malformed => f.write_str("malformed markup"),
+        }
+    }
+}
+
+impl From<fmt::Error> for DecodeError {
+    fn from(_: fmt::Error) -> Self {
+        DecodeError::Fmt
+    }
+}
+
+fn parse_radix(digits: &[u8], radix: u32) -> Option<u32> {
+    if digits.is_empty() {
+        return None;
+    }
+    digits.iter().try_fold(0u32, |acc, &b| {
+        let digit = char::from(b).to_digit(radix)?;
+        acc.checked_mul(radix)?.checked_add(digit)
+    })
+}
+
+/// Check whether `c` matches the `Char` production of XML 1.0
+fn is_xml_char(c: char) -> bool {
+    matches!(c, '\t' | '\n' | '\r' | '\u{20}'..='\u{D7FF}' | '\u{E000}'..='\u{FFFD}' | '\u{10000}'..)
+}
+
+/// Resolve a predefined entity or character reference (`&...;`) to a `char`
+///
+/// Character references to code points that are not an XML `Char` are rejected,
+/// i.e. surrogates (`&#xD800;`..=`&#xDFFF;`), anything above `&#x10FFFF;`,
+/// `&#xFFFE;`, `&#xFFFF;` and control characters other than tab, line feed
+/// and carriage return, including `&#0;`.
+pub(crate) fn decode_reference(entity: &[u8]) -> Option<char> {
+    let name = entity.strip_prefix(b"&")?.strip_suffix(b";")?;
+    match name {
+        b"amp" => Some('&'),
+        b"lt" => Some('<'),
+        b"gt" => Some('>'),
+        b"quot" => Some('"'),
+        b"apos" => Some('\''),
+        _ => {
+            let number = name.strip_prefix(b"#")?;
+            let value = match number.strip_prefix(b"x") {
+                Some(hex) => parse_radix(hex, 16)?,
+                None => parse_radix(number, 10)?,
+            };
+            char::from_u32(value).filter(|&c| is_xml_char(c))
+        }
+    }
+}
+
+/// Check whether `bytes` start with a complete entity or character reference
+///
+/// This only checks the syntax, i.e. the reference does not need to be known.
+pub(crate) fn is_reference(bytes: &[u8]) -> bool {
+    let Some(inner) = bytes.strip_prefix(b"&") else {
+        return false;
+    };
+    let Some(end) = inner
+        .iter()
+        .position(|&b| matches!(b, b';' | b'&' | b'<') || crate::tag::is_space(b))
+    else {
+        return false;
+    };
+    if inner[end] != b';' {
+        return false;
+    }
+    let name = &inner[..end];
+    match name.strip_prefix(b"#") {
+        Some(number) => match number.strip_prefix(b"x") {
+            Some(hex) => parse_radix(hex, 16).is_some(),
+            None => parse_radix(number, 10).is_some(),
+        },
+        None => crate::tag::is_name(name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_reference, is_reference};
+
+    #[test]
+    fn test_is_reference() {
+        assert!(is_reference(b"&amp; b"));
+        assert!(is_reference(b"&nbsp;"));
+        assert!(is_reference(b"&#65;"));
+        assert!(is_reference(b"&#x1F600;"));
+        assert!(!is_reference(b"& b;"));
+        assert!(!is_reference(b"&&"));
+        assert!(!is_reference(b"&amp"));
+        assert!(!is_reference(b"&#x;"));
+        assert!(!is_reference(b"&1;"));
+    }
+
+    #[test]
+    fn test_decode_reference() {
+        assert_eq!(decode_reference(b"&amp;"), Some('&'));
+        assert_eq!(decode_reference(b"&apos;"), Some('\''));
+        assert_eq!(decode_reference(b"&#65;"), Some('A'));
+        assert_eq!(decode_reference(b"&#x1F600;"), Some('\u{1F600}'));
+        assert_eq!(decode_reference(b"&nbsp;"), None);
+        assert_eq!(decode_reference(b"&#;"), None);
+        assert_eq!(decode_reference(b"&#x;"), None);
+        assert_eq!(decode_reference(b"&#+1;"), None);
+    }
+
+    #[test]
+    fn test_decode_invalid_code_points() {
+        assert_eq!(decode_reference(b"&#xD800;"), None);
+        assert_eq!(decode_reference(b"&#xDFFF;"), None);
+        assert_eq!(decode_reference(b"&#55296;"), None);
+        assert_eq!(decode_reference(b"&#x110000;"), None);
+        assert_eq!(decode_reference(b"&#xFFFFFFFF;"), None);
+        assert_eq!(decode_reference(b"&#x100000000;"), None);
+        assert_eq!(decode_reference(b"&#99999999999;"), None);
+        assert_eq!(decode_reference(b"&#x10FFFF;"), Some('\u{10FFFF}'));
+        assert_eq!(decode_reference(b"&#xD7FF;"), Some('\u{D7FF}'));
+        assert_eq!(decode_reference(b"&#xE000;"), Some('\u{E000}'));
+    }
+
+    #[test]
+    fn test_decode_non_xml_chars() {
+        assert_eq!(decode_reference(b"&#0;"), None);
+        assert_eq!(decode_reference(b"&#x0;"), None);
+        assert_eq!(decode_reference(b"&#x1;"), None);
+        assert_eq!(decode_reference(b"&#x1F;"), None);
+        assert_eq!(decode_reference(b"&#xFFFE;"), None);
+        assert_eq!(decode_reference(b"&#xFFFF;"), None);
+        assert_eq!(decode_reference(b"&#9;"), Some('\t'));
+        assert_eq!(decode_reference(b"&#xA;"), Some('\n'));
+        assert_eq!(decode_reference(b"&#13;"), Some('\r'));
+        assert_eq!(decode_reference(b"&#x20;"), Some(' '));
+        assert_eq!(decode_reference(b"&#xFFFD;"), Some('\u{FFFD}'));
+        assert_eq!(decode_reference(b"&#x10000;"), Some('\u{10000}'));
+    }
+}
@@ -0,0 +1,262 @@
+//! A higher-level event layer on top of the [`Tokenizer`]
+
+use crate::{tag, Token, Tokenizer};
+
+/// A semantic XML event, see [`Tokenizer::events`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event<'a> {
+    /// Start tag (i.e. `<name ...>`)
+    Open { name: &'a [u8], attrs_raw: &'a [u8] },
+    /// End tag (i.e. `</name>`)
+    Close { name: &'a [u8] },
+    /// Self-closing tag (i.e. `<name .../>`)
+    Empty { name: &'a [u8], attrs_raw: &'a [u8] },
+    /// Character data, the content of a CDATA section or an (undecoded) entity
+    Text(&'a [u8]),
+    /// Comment (i.e. `<!-- ... -->`)
+    Comment(&'a [u8]),
+    /// Processing Instruction (i.e. `<? ... ?>`)
+    Pi(&'a [u8]),
+    /// Malformed input
+    Error(&'a [u8]),
+}
+
+/// Iterator over the [`Event`]s of a document
+///
+/// Declarations of the DTD and a Byte Order Mark are skipped.
+///
+/// The names of open elements are kept on a stack of at most
+/// [`Events::MAX_DEPTH`] entries, so that an end tag that does not match
+/// the innermost open element (or any element at all) is reported as an
+/// [`Event::Error`] and leaves the stack as it was. A start tag that would
+/// exceed the limit is reported as an [`Event::Error`], as is the end tag
+/// that closes it, whose name can't be checked.
+#[derive(Debug, Clone)]
+pub struct Events<'a> {
+    tokens: Tokenizer<'a>,
+    peeked: Option<Option<Event<'a>>>,
+    stack: NameStack<'a>,
+}
+
+impl<'a> Events<'a> {
+    /// The maximum depth of nested elements
+    pub const MAX_DEPTH: usize = 32;
+
+    pub(crate) fn new(tokens: Tokenizer<'a>) -> Self {
+        Self {
+            tokens,
+            peeked: None,
+            stack: NameStack {
+                names: [&[]; Events::MAX_DEPTH],
+                depth: 0,
+                overflow: 0,
+            },
+        }
+    }
+
+    /// The number of elements that are currently open
+    pub fn depth(&self) -> usize {
+        self.stack.depth + self.stack.overflow
+    }
+
+    /// Look at the next event without consuming it
+    pub fn peek_event(&mut self) -> Option<&Event<'a>> {
+        let (tokens, stack) = (&mut self.tokens, &mut self.stack);
+        self.peeked
+            .get_or_insert_with(|| next_event(tokens, stack))
+            .as_ref()
+    }
+}
+
+/// The names of the open elements
+#[derive(Debug, Clone)]
+struct NameStack<'a> {
+    names: [&'a [u8]; Events::MAX_DEPTH],
+    depth: usize,
+    /// Number of open elements beyond [`Events::MAX_DEPTH`]
+    overflow: usize,
+}
+
+impl<'a> NameStack<'a> {
+    fn open(&mut self, name: &'a [u8]) -> bool {
+        match self.names.get_mut(self.depth) {
+            Some(entry) if self.overflow == 0 => {
+                *entry = name;
+                self.depth += 1;
+                true
+            }
+            _ => {
+                self.overflow += 1;
+                false
+            }
+        }
+    }
+
+    fn close(&mut self, name: &[u8]) -> bool {
+        if self.overflow > 0 {
+            self.overflow -= 1;
+            return false;
+        }
+        match self.depth.checked_sub(1) {
+            Some(top) if self.names[top] == name => {
+                self.depth = top;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+fn next_event<'a>(tokens: &mut Tokenizer<'a>, stack: &mut NameStack<'a>) -> Option<Event<'a>> {
+    loop {
+        let event = match tokens.next()? {
+            Token::Span(span) | Token::Entity(span) => Event::Text(span),
+            Token::Error(span) => Event::Error(span),
+            Token::PI(span) | Token::XmlDecl(span) => Event::Pi(span),
+            Token::Comment(span) => Event::Comment(span),
+            Token::CData(span) => Event::Text(&span[9..(span.len() - 3)]),
+            Token::Decl(_)
+            | Token::SubsetStart(_)
+            | Token::DeclEnd(_)
+            | Token::CondOpen(_)
+            | Token::CondClose(_)
+            | Token::Bom(_)
+            | Token::Eof(_) => continue,
+            Token::EmptyElement(span) => match tag::parse_element(span) {
+                Some(e) => Event::Empty {
+                    name: e.name(),
+                    attrs_raw: e.attributes_raw(),
+                },
+                None => Event::Error(span),
+            },
+            Token::Element(span) => match tag::parse_element(span) {
+                Some(e) if stack.open(e.name()) => Event::Open {
+                    name: e.name(),
+                    attrs_raw: e.attributes_raw(),
+                },
+                _ => Event::Error(span),
+            },
+            Token::ElementEnd(span) => match tag::element_name(span) {
+                Some(name) if stack.close(name) => Event::Close { name },
+                _ => Event::Error(span),
+            },
+        };
+        break Some(event);
+    }
+}
+
+impl<'a> Iterator for Events<'a> {
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.peeked.take() {
+            Some(event) => event,
+            None => next_event(&mut self.tokens, &mut self.stack),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::{Event, Events};
+    use crate::Tokenizer;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_peek_event() {
+        let mut events = Tokenizer::new(b"<!DOCTYPE a><a x='1'>hi<b/></a>").events();
+        let open = Event::Open {
+            name: b"a",
+            attrs_raw: b" x='1'",
+        };
+        assert_eq!(events.peek_event(), Some(&open));
+        assert_eq!(events.peek_event(), Some(&open));
+        assert_eq!(events.next(), Some(open));
+        assert_eq!(events.peek_event(), Some(&Event::Text(b"hi")));
+        assert_eq!(events.next(), Some(Event::Text(b"hi")));
+        assert_eq!(
+            events.next(),
+            Some(Event::Empty {
+                name: b"b",
+                attrs_raw: b""
+            })
+        );
+        assert_eq!(events.peek_event(), Some(&Event::Close { name: b"a" }));
+        assert_eq!(events.next(), Some(Event::Close { name: b"a" }));
+        assert_eq!(events.peek_event(), None);
+        assert_eq!(events.next(), None);
+    }
+
+    #[test]
+    fn test_cdata_event() {
+        let mut events = Tokenizer::new(b"<a><![CDATA[<b>]]></a>").events();
+        assert_eq!(events.nth(1), Some(Event::Text(b"<b>")));
+    }
+
+    #[test]
+    fn test_nested_events() {
+        let events = Tokenizer::new(b"<a><b x='1'>t</b><c/></a>")
+            .events()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            [
+                Event::Open {
+                    name: b"a",
+                    attrs_raw: b""
+                },
+                Event::Open {
+                    name: b"b",
+                    attrs_raw: b" x='1'"
+                },
+                Event::Text(b"t"),
+                Event::Close { name: b"b" },
+                Event::Empty {
+                    name: b"c",
+                    attrs_raw: b""
+                },
+                Event::Close { name: b"a" },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mismatched_close() {
+        let mut events = Tokenizer::new(b"<a></b></a></a>").events();
+        assert_eq!(
+            events.next(),
+            Some(Event::Open {
+                name: b"a",
+                attrs_raw: b""
+            })
+        );
+        assert_eq!(events.next(), Some(Event::Error(b"</b>")));
+        assert_eq!(events.depth(), 1);
+        assert_eq!(events.next(), Some(Event::Close { name: b"a" }));
+        assert_eq!(events.depth(), 0);
+        assert_eq!(events.next(), Some(Event::Error(b"</a>")));
+        assert_eq!(events.next(), None);
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let mut input = Vec::new();
+        for _ in 0..=Events::MAX_DEPTH {
+            input.extend_from_slice(b"<a>");
+        }
+        input.extend_from_slice(b"<b/>");
+        for _ in 0..=Events::MAX_DEPTH {
+            input.extend_from_slice(b"</a>");
+        }
+        let events = Tokenizer::new(&input).events().collect::<Vec<_>>();
+        let (opens, rest) = events.split_at(Events::MAX_DEPTH);
+        assert!(opens.iter().all(|e| matches!(e, Event::Open { .. })));
+        assert_eq!(rest[0], Event::Error(b"<a>"));
+        assert!(matches!(rest[1], Event::Empty { .. }));
+        assert_eq!(rest[2], Event::Error(b"</a>"));
+        assert!(rest[3..].iter().all(|e| matches!(e, Event::Close { .. })));
+        assert_eq!(rest.len(), 3 + Events::MAX_DEPTH);
+    }
+}
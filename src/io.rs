@@ -0,0 +1,291 @@
+//! Tokenizing input that is pulled from a reader into a fixed buffer
+
+use crate::{Token, Tokenizer, TokenizerOptions};
+
+/// A source of bytes, modelled after `std::io::Read`
+///
+/// This is a minimal, `no_std` compatible trait so that readers from crates
+/// like `embedded-io` or `core2` can be plugged in via a small wrapper.
+pub trait Read {
+    type Error;
+
+    /// Read some bytes into `buf`, returning how many were read
+    ///
+    /// Returning `Ok(0)` for a non-empty `buf` signals the end of the input.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error>;
+}
+
+impl Read for &[u8] {
+    type Error = core::convert::Infallible;
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let len = buf.len().min(self.len());
+        let (head, tail) = self.split_at(len);
+        buf[..len].copy_from_slice(head);
+        *self = tail;
+        Ok(len)
+    }
+}
+
+/// The state of a [`Tokenizer`] that is carried from one buffer to the next
+#[derive(Debug, Clone, Copy)]
+struct Carry {
+    dtd_depth: usize,
+    subset_start: bool,
+    ignore_section: bool,
+    raw_text: Option<&'static [u8]>,
+    check_bom: bool,
+    doc_start: bool,
+    eof_emitted: bool,
+}
+
+impl Carry {
+    fn of(tokens: &Tokenizer<'_>) -> Self {
+        Self {
+            dtd_depth: tokens.dtd_depth,
+            subset_start: tokens.subset_start,
+            ignore_section: tokens.ignore_section,
+            raw_text: tokens.raw_text,
+            check_bom: tokens.check_bom,
+            doc_start: tokens.doc_start,
+            eof_emitted: tokens.eof_emitted,
+        }
+    }
+
+    fn tokenizer(self, bytes: &[u8], options: TokenizerOptions, partial: bool) -> Tokenizer<'_> {
+        Tokenizer {
+            dtd_depth: self.dtd_depth,
+            subset_start: self.subset_start,
+            ignore_section: self.ignore_section,
+            raw_text: self.raw_text,
+            partial,
+            check_bom: self.check_bom,
+            doc_start: self.doc_start,
+            eof_emitted: self.eof_emitted,
+            ..Tokenizer::new(bytes).with_options(options)
+        }
+    }
+}
+
+/// A tokenizer that pulls its input from a [`Read`]er into a caller-provided buffer
+///
+/// Tokens borrow from the buffer, so they are returned from [`ReaderTokenizer::next_token`]
+/// rather than through [`Iterator`]. The tokens are the same as those produced by a
+/// [`Tokenizer`] over the complete input, as long as every token fits into the buffer.
+/// A token that is larger than the buffer is returned as a `Token::Error` of the
+/// whole buffer, and tokenizing continues after it.
+pub struct ReaderTokenizer<'b, R> {
+    reader: R,
+    buf: &'b mut [u8],
+    start: usize,
+    end: usize,
+    carry: Carry,
+    options: TokenizerOptions,
+    eof: bool,
+}
+
+impl<'b, R: Read> ReaderTokenizer<'b, R> {
+    /// Create a new tokenizer reading from `reader` using `buf` as its buffer
+    pub fn new(reader: R, buf: &'b mut [u8]) -> Self {
+        Self {
+            reader,
+            buf,
+            start: 0,
+            end: 0,
+            carry: Carry::of(&Tokenizer::new(b"")),
+            options: TokenizerOptions::default(),
+            eof: false,
+        }
+    }
+
+    /// Tokenize with the given options, see [`Tokenizer::with_options`]
+    pub fn with_options(self, options: TokenizerOptions) -> Self {
+        Self { options, ..self }
+    }
+
+    /// Returns `Ok(false)` if the buffer is full
+    fn fill(&mut self) -> Result<bool, R::Error> {
+        self.buf.copy_within(self.start..self.end, 0);
+        self.end -= self.start;
+        self.start = 0;
+        if self.end == self.buf.len() {
+            return Ok(false);
+        }
+        let len = self.reader.read(&mut self.buf[self.end..])?;
+        self.end += len;
+        self.eof = len == 0;
+        Ok(true)
+    }
+
+    /// Read the next token, pulling more input from the reader as needed
+    pub fn next_token(&mut self) -> Option<Result<Token<'_>, R::Error>> {
+        let mut partial = !self.eof;
+        while partial {
+            let bytes = &self.buf[self.start..self.end];
+            if self
+                .carry
+                .tokenizer(bytes, self.options, true)
+                .next()
+                .is_some()
+            {
+                break;
+            }
+            match self.fill() {
+                Ok(true) => partial = !self.eof,
+                Ok(false) => partial = false,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        let bytes = &self.buf[self.start..self.end];
+        let mut tokens = self.carry.tokenizer(bytes, self.options, false);
+        let token = tokens.next()?;
+        self.start = self.end - tokens.rest.len();
+        self.carry = Carry::of(&tokens);
+        Some(Ok(token))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::{Read, ReaderTokenizer};
+    use crate::{Token, Tokenizer, TokenizerOptions};
+    use std::vec::Vec;
+
+    /// Reader that returns at most `chunk` bytes per call
+    struct Chunked<'a> {
+        bytes: &'a [u8],
+        chunk: usize,
+    }
+
+    impl Read for Chunked<'_> {
+        type Error = ();
+
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, ()> {
+            let len = buf.len().min(self.chunk);
+            let mut head = &self.bytes[..len.min(self.bytes.len())];
+            self.bytes = &self.bytes[head.len()..];
+            head.read(buf).map_err(|_| ())
+        }
+    }
+
+    const INPUT: &[u8] =
+        b"\xEF\xBB\xBF<?xml version='1.0'?>\n<!DOCTYPE a [\n<!ELEMENT a ANY>\n]>\n\
+        <a x=\"1 > 0\"><!-- comment -->Tom &amp; Jerry<b/></a>\n";
+
+    #[test]
+    fn test_reader_tokenizer() {
+        let expected = Tokenizer::new(INPUT).collect::<Vec<_>>();
+        for chunk in 1..8 {
+            let mut buf = [0; 32];
+            let reader = Chunked {
+                bytes: INPUT,
+                chunk,
+            };
+            let mut tokens = ReaderTokenizer::new(reader, &mut buf);
+            for expected in &expected {
+                assert_eq!(tokens.next_token(), Some(Ok(expected.clone())));
+            }
+            assert_eq!(tokens.next_token(), None);
+        }
+    }
+
+    #[test]
+    fn test_reader_ignore_section() {
+        let input = b"<!DOCTYPE a [<![IGNORE[ <x> ]]>]><a/>";
+        let expected = Tokenizer::new(input).collect::<Vec<_>>();
+        assert!(expected.contains(&Token::Span(b" <x> ")));
+        for chunk in 1..8 {
+            let mut buf = [0; 16];
+            let reader = Chunked {
+                bytes: input,
+                chunk,
+            };
+            let mut tokens = ReaderTokenizer::new(reader, &mut buf);
+            for expected in &expected {
+                assert_eq!(tokens.next_token(), Some(Ok(expected.clone())));
+            }
+            assert_eq!(tokens.next_token(), None);
+        }
+    }
+
+    #[test]
+    fn test_reader_options() {
+        let input = b"<p><script>if (a<b && c) x();</script>a < b<br></p>";
+        let html = Tokenizer::new_html(input);
+        let options = html.options();
+        let expected = html.collect::<Vec<_>>();
+        assert!(expected.contains(&Token::Span(b"if (a<b && c) x();")));
+        for chunk in 1..8 {
+            let mut buf = [0; 32];
+            let reader = Chunked {
+                bytes: input,
+                chunk,
+            };
+            let mut tokens = ReaderTokenizer::new(reader, &mut buf).with_options(options);
+            for expected in &expected {
+                assert_eq!(tokens.next_token(), Some(Ok(expected.clone())));
+            }
+            assert_eq!(tokens.next_token(), None);
+        }
+
+        let options = TokenizerOptions {
+            emit_eof: true,
+            ..TokenizerOptions::default()
+        };
+        let mut buf = [0; 8];
+        let mut tokens = ReaderTokenizer::new(&b"<a/>"[..], &mut buf).with_options(options);
+        assert_eq!(tokens.next_token(), Some(Ok(Token::EmptyElement(b"<a/>"))));
+        assert_eq!(tokens.next_token(), Some(Ok(Token::Eof(b""))));
+        assert_eq!(tokens.next_token(), None);
+    }
+
+    #[test]
+    fn test_reader_xml_decl() {
+        for input in [
+            &b"<a/><?xml version='1.0'?>"[..],
+            b"\xEF\xBB\xBF<?xml version='1.0'?><a/>",
+        ] {
+            let expected = Tokenizer::new(input).collect::<Vec<_>>();
+            let mut buf = [0; 32];
+            let mut tokens = ReaderTokenizer::new(input, &mut buf);
+            for expected in &expected {
+                assert_eq!(tokens.next_token(), Some(Ok(expected.clone())));
+            }
+            assert_eq!(tokens.next_token(), None);
+        }
+        let mut buf = [0; 32];
+        let mut tokens = ReaderTokenizer::new(&b"<a/><?xml version='1.0'?>"[..], &mut buf);
+        assert_eq!(tokens.next_token(), Some(Ok(Token::EmptyElement(b"<a/>"))));
+        assert_eq!(
+            tokens.next_token(),
+            Some(Ok(Token::PI(b"<?xml version='1.0'?>")))
+        );
+    }
+
+    #[test]
+    fn test_reader_token_too_large() {
+        let mut buf = [0; 4];
+        let mut tokens = ReaderTokenizer::new(&b"<abcdef>x"[..], &mut buf);
+        assert_eq!(tokens.next_token(), Some(Ok(Token::Error(b"<abc"))));
+        assert_eq!(tokens.next_token(), Some(Ok(Token::Span(b"def>"))));
+        assert_eq!(tokens.next_token(), Some(Ok(Token::Span(b"x"))));
+        assert_eq!(tokens.next_token(), None);
+    }
+
+    #[test]
+    fn test_reader_error() {
+        struct Failing;
+        impl Read for Failing {
+            type Error = &'static str;
+
+            fn read(&mut self, _: &mut [u8]) -> Result<usize, Self::Error> {
+                Err("broken pipe")
+            }
+        }
+        let mut buf = [0; 8];
+        let mut tokens = ReaderTokenizer::new(Failing, &mut buf);
+        assert_eq!(tokens.next_token(), Some(Err("broken pipe")));
+    }
+}
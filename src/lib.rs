@@ -28,6 +28,52 @@
 //! ]);
 //! ```
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+use alloc::borrow::Cow;
+use core::ops::Range;
+
+mod builder;
+mod canonical;
+mod comment;
+mod document;
+mod dtd;
+mod entity;
+mod events;
+#[cfg(feature = "io")]
+mod io;
+#[cfg(feature = "alloc")]
+mod owned;
+mod pi;
+mod position;
+mod scan;
+mod stats;
+mod str_token;
+mod tag;
+mod text;
+mod validate;
+
+pub use builder::TokenizerBuilder;
+pub use canonical::{write_canonical, write_canonical_with_comments};
+pub use comment::comment_directive;
+pub use document::{find_all_elements, split_document};
+pub use dtd::{element_decl, entity_decl, ContentModel, DeclKind, Doctype, EntityDecl};
+pub use entity::DecodeError;
+pub use events::{Event, Events};
+#[cfg(feature = "io")]
+pub use io::{Read, ReaderTokenizer};
+#[cfg(feature = "alloc")]
+pub use owned::OwnedToken;
+pub use position::{LineCol, Position};
+pub use scan::{count_elements, markup_density, MarkupStats};
+pub use stats::TokenStats;
+pub use str_token::StrToken;
+pub use tag::{parse_element, AttributeMode, Attributes, CheckedAttributes, ParsedElement};
+pub use text::{MergedToken, TextEvent};
+pub use validate::ValidationReport;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Token<'a> {
     /// Non-Syntax
@@ -38,33 +84,1225 @@ pub enum Token<'a> {
     Error(&'a [u8]),
     /// Processing Instruction (i.e. `<? ... ?>`)
     PI(&'a [u8]),
+    /// XML declaration (i.e. `<?xml ... ?>`) at the start of the input
+    XmlDecl(&'a [u8]),
     /// Comment (i.e. `<!-- ... -->`)
     Comment(&'a [u8]),
+    /// CDATA section (i.e. `<![CDATA[ ... ]]>`)
+    CData(&'a [u8]),
     /// Structural Declaration, e.g. `<!DOCTYPE ... >`
+    ///
+    /// A declaration with an internal subset (e.g. `<!DOCTYPE a [ ... ]>`) ends
+    /// before the `[`, which is emitted as a `SubsetStart` token.
     Decl(&'a [u8]),
+    /// Start of the internal subset of a `Decl` (i.e. `[`)
+    SubsetStart(&'a [u8]),
     /// End of `Decl` with body (e.g. `]>`)
     DeclEnd(&'a [u8]),
-    /// Element
+    /// Start of a conditional section in a DTD (e.g. `<![INCLUDE[` or `<![ %draft; [`)
+    ///
+    /// The content of an `IGNORE` section is not tokenized, but returned as a
+    /// single `Span` before the `CondClose`.
+    CondOpen(&'a [u8]),
+    /// End of a conditional section (i.e. `]]>`)
+    CondClose(&'a [u8]),
+    /// Start tag of an element (i.e. `<...>`)
     Element(&'a [u8]),
+    /// Empty element tag (i.e. `<.../>`)
+    EmptyElement(&'a [u8]),
     /// End of Element (i.e. `</...>`)
     ElementEnd(&'a [u8]),
+    /// UTF-8 Byte Order Mark (i.e. `EF BB BF`) at the start of the input
+    Bom(&'a [u8]),
+    /// End of input, see [`Tokenizer::set_emit_eof`]
+    ///
+    /// This is the only token with an empty slice.
+    Eof(&'a [u8]),
+}
+
+/// The kind of a [`Token`], without its bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    /// See [`Token::Span`]
+    Span,
+    /// See [`Token::Entity`]
+    Entity,
+    /// See [`Token::Error`]
+    Error,
+    /// See [`Token::PI`]
+    PI,
+    /// See [`Token::XmlDecl`]
+    XmlDecl,
+    /// See [`Token::Comment`]
+    Comment,
+    /// See [`Token::CData`]
+    CData,
+    /// See [`Token::Decl`]
+    Decl,
+    /// See [`Token::SubsetStart`]
+    SubsetStart,
+    /// See [`Token::DeclEnd`]
+    DeclEnd,
+    /// See [`Token::CondOpen`]
+    CondOpen,
+    /// See [`Token::CondClose`]
+    CondClose,
+    /// See [`Token::Element`]
+    Element,
+    /// See [`Token::EmptyElement`]
+    EmptyElement,
+    /// See [`Token::ElementEnd`]
+    ElementEnd,
+    /// See [`Token::Bom`]
+    Bom,
+    /// See [`Token::Eof`]
+    Eof,
+}
+
+impl<'a> Token<'a> {
+    /// The kind of this token
+    pub fn kind(&self) -> TokenKind {
+        match self {
+            Token::Span(_) => TokenKind::Span,
+            Token::Entity(_) => TokenKind::Entity,
+            Token::Error(_) => TokenKind::Error,
+            Token::PI(_) => TokenKind::PI,
+            Token::XmlDecl(_) => TokenKind::XmlDecl,
+            Token::Comment(_) => TokenKind::Comment,
+            Token::CData(_) => TokenKind::CData,
+            Token::Decl(_) => TokenKind::Decl,
+            Token::SubsetStart(_) => TokenKind::SubsetStart,
+            Token::DeclEnd(_) => TokenKind::DeclEnd,
+            Token::CondOpen(_) => TokenKind::CondOpen,
+            Token::CondClose(_) => TokenKind::CondClose,
+            Token::Element(_) => TokenKind::Element,
+            Token::EmptyElement(_) => TokenKind::EmptyElement,
+            Token::ElementEnd(_) => TokenKind::ElementEnd,
+            Token::Bom(_) => TokenKind::Bom,
+            Token::Eof(_) => TokenKind::Eof,
+        }
+    }
+
+    /// The bytes of the input that this token covers
+    ///
+    /// The tokens of a [`Tokenizer`] partition its input, so joining the bytes
+    /// of all tokens yields the input again.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        match *self {
+            Token::Span(span)
+            | Token::Entity(span)
+            | Token::Error(span)
+            | Token::PI(span)
+            | Token::XmlDecl(span)
+            | Token::Comment(span)
+            | Token::CData(span)
+            | Token::Decl(span)
+            | Token::SubsetStart(span)
+            | Token::DeclEnd(span)
+            | Token::CondOpen(span)
+            | Token::CondClose(span)
+            | Token::Element(span)
+            | Token::EmptyElement(span)
+            | Token::ElementEnd(span)
+            | Token::Bom(span)
+            | Token::Eof(span) => span,
+        }
+    }
+
+    /// The kind of a `Decl` token, or `None` for all other tokens
+    pub fn decl_kind(&self) -> Option<DeclKind> {
+        match self {
+            Token::Decl(decl) => dtd::decl_kind(decl),
+            _ => None,
+        }
+    }
+
+    /// The name and value or external identifier of an `<!ENTITY ...>` declaration
+    ///
+    /// Returns `None` for other tokens and declarations, see [`entity_decl`].
+    pub fn entity_decl(&self) -> Option<EntityDecl<'a>> {
+        match self {
+            Token::Decl(decl) => dtd::entity_decl(decl),
+            _ => None,
+        }
+    }
+
+    /// The name and external identifiers of a `<!DOCTYPE ...>` declaration
+    ///
+    /// Returns `None` for other tokens and declarations.
+    pub fn doctype(&self) -> Option<Doctype<'a>> {
+        match self {
+            Token::Decl(decl) => dtd::doctype_decl(decl),
+            _ => None,
+        }
+    }
+
+    /// Check whether a start or empty element tag declares an attribute name more than once
+    ///
+    /// Names are compared byte for byte, without resolving namespace prefixes.
+    /// Returns `false` for all other tokens.
+    pub fn has_duplicate_attributes(&self) -> bool {
+        let Some(mut attrs) = self.attributes() else {
+            return false;
+        };
+        while let Some((name, _)) = attrs.next() {
+            if attrs.clone().any(|(other, _)| other == name) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// The bytes of this token, with `\r\n` and a lone `\r` replaced by `\n`
+    ///
+    /// This implements the end-of-line handling of XML 1.0 for the content of
+    /// `Span`, `CData` and `Entity` tokens. The bytes of all other tokens are
+    /// returned unchanged.
+    pub fn normalized_newlines(&self) -> impl Iterator<Item = u8> + 'a {
+        let bytes = self.as_bytes();
+        let normalize = matches!(self, Token::Span(_) | Token::CData(_) | Token::Entity(_));
+        bytes.iter().enumerate().filter_map(move |(i, &b)| match b {
+            b'\r' if normalize && bytes.get(i + 1) == Some(&b'\n') => None,
+            b'\r' if normalize => Some(b'\n'),
+            b => Some(b),
+        })
+    }
+
+    /// Check whether this is a `Token::Error`
+    pub fn is_error(&self) -> bool {
+        matches!(self, Token::Error(_))
+    }
+
+    /// Check whether this is a whitespace-only `Span` that can be ignored
+    ///
+    /// `context` is the content model of the enclosing element, e.g. from
+    /// [`element_decl`]. Whitespace within element-only (or empty) content is
+    /// ignorable, while it is significant in mixed content and content declared
+    /// as `ANY`. Without a content model, all whitespace between markup is
+    /// considered potentially ignorable.
+    pub fn is_ignorable_whitespace(&self, context: Option<ContentModel>) -> bool {
+        self.is_whitespace() && !matches!(context, Some(ContentModel::Mixed | ContentModel::Any))
+    }
+
+    /// Check whether this is a `Span` that only consists of spaces, tabs, carriage returns and line feeds
+    pub fn is_whitespace(&self) -> bool {
+        matches!(self, Token::Span(span) if span.iter().all(|&b| tag::is_space(b)))
+    }
+
+    /// Resolve an `Entity` token that is a predefined entity or character reference
+    ///
+    /// Returns `None` for all other tokens, for other entities like `&nbsp;`
+    /// and for character references to code points that are not an XML `Char`,
+    /// like `&#0;` or a surrogate.
+    pub fn decode_entity(&self) -> Option<char> {
+        match self {
+            Token::Entity(entity) => entity::decode_reference(entity),
+            _ => None,
+        }
+    }
+
+    /// The qualified name of an `Element`, `EmptyElement` or `ElementEnd` token
+    ///
+    /// Returns `None` for all other tokens.
+    pub fn name(&self) -> Option<&'a [u8]> {
+        match *self {
+            Token::Element(span) | Token::EmptyElement(span) | Token::ElementEnd(span) => {
+                tag::element_name(span)
+            }
+            _ => None,
+        }
+    }
+
+    /// The namespace prefix (if any) and the local part of [`Token::name`]
+    ///
+    /// The name is split at the first colon, so `<a:b:c>` has the local name
+    /// `b:c`. A name that starts or ends with a colon has no prefix.
+    pub fn qname(&self) -> Option<(Option<&'a [u8]>, &'a [u8])> {
+        self.name().map(tag::split_qname)
+    }
+
+    fn pi_target_data(&self) -> Option<(&'a [u8], &'a [u8])> {
+        match *self {
+            Token::PI(span) | Token::XmlDecl(span) => pi::pi_target_data(span),
+            _ => None,
+        }
+    }
+
+    /// The target of a `PI` or `XmlDecl` token, e.g. `xml-stylesheet`
+    ///
+    /// Returns `None` for all other tokens.
+    pub fn pi_target(&self) -> Option<&'a [u8]> {
+        self.pi_target_data().map(|(target, _)| target)
+    }
+
+    /// The data of a `PI` or `XmlDecl` token, i.e. everything after the target and whitespace
+    ///
+    /// This is empty for a processing instruction without data like `<?foo?>`.
+    /// Returns `None` for all other tokens.
+    pub fn pi_data(&self) -> Option<&'a [u8]> {
+        self.pi_target_data().map(|(_, data)| data)
+    }
+
+    fn xml_decl_data(&self) -> Option<&'a [u8]> {
+        match self.pi_target_data()? {
+            (b"xml", data) => Some(data),
+            _ => None,
+        }
+    }
+
+    /// The `encoding` of an XML declaration, e.g. `UTF-8`
+    ///
+    /// This accepts an `XmlDecl` token as well as a `PI` with the target `xml`.
+    /// Returns `None` if the encoding is not declared, as it is optional, or
+    /// for all other tokens.
+    pub fn declared_encoding(&self) -> Option<&'a [u8]> {
+        pi::pseudo_attribute(self.xml_decl_data()?, b"encoding")
+    }
+
+    /// The `version` of an XML declaration, e.g. `1.0`
+    ///
+    /// See [`Token::declared_encoding`] for the tokens that are accepted.
+    pub fn declared_version(&self) -> Option<&'a [u8]> {
+        pi::pseudo_attribute(self.xml_decl_data()?, b"version")
+    }
+
+    /// Iterator over the attributes of an `Element`, `EmptyElement` or `ElementEnd` token
+    ///
+    /// Returns `None` for all other tokens. See [`Attributes`] for details.
+    pub fn attributes(&self) -> Option<Attributes<'a>> {
+        match *self {
+            Token::Element(span) | Token::EmptyElement(span) | Token::ElementEnd(span) => {
+                tag::tag_attributes(span)
+            }
+            _ => None,
+        }
+    }
+
+    /// Check whether the bytes of this token contain `b`
+    pub fn contains_byte(&self, b: u8) -> bool {
+        memchr::memchr(b, self.as_bytes()).is_some()
+    }
+
+    /// Check whether the bytes of this token are `other`, see [`Token::as_bytes`]
+    ///
+    /// The kind of the token is ignored, so `Token::Span(b"<a>").bytes_eq("<a>")`
+    /// holds just like `Token::Element(b"<a>").bytes_eq("<a>")`. Compare with
+    /// another `Token` to check the kind as well.
+    pub fn bytes_eq(&self, other: impl AsRef<[u8]>) -> bool {
+        self.as_bytes() == other.as_ref()
+    }
+}
+
+/// Kind of a malformed token, see [`Tokenizer::checked_next`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// Comment without `-->`
+    UnterminatedComment,
+    /// CDATA section without `]]>`
+    UnterminatedCData,
+    /// Processing Instruction without `?>`
+    UnterminatedPi,
+    /// Declaration or end of a DTD subset without `>`
+    UnterminatedDecl,
+    /// Entity without `;`
+    UnterminatedEntity,
+    /// Element or end of element without `>`
+    UnterminatedTag,
+    /// Element or end of element whose name does not start with a name character, like `<@>`
+    InvalidTagStart,
+    /// Conditional section whose keyword is not `INCLUDE`, `IGNORE` or a parameter entity, like `<![FOO[`
+    InvalidCondSection,
+    /// `<!` that does not start a comment, CDATA section, declaration or conditional section, like `<!1>`
+    InvalidDecl,
+    /// Entity with a name that is not an XML name (strict mode only)
+    InvalidEntityName,
+    /// Processing Instruction with a reserved target like `xmlfoo` (strict mode only)
+    ReservedPiTarget,
+    /// Comment that contains `--` before its end (strict mode only)
+    DoubleHyphenInComment,
+    /// Text that contains `]]>` outside of a CDATA section (strict mode only)
+    CDataEndInText,
+    /// Control character that is not allowed in XML 1.0, see [`Tokenizer::set_reject_control_chars`]
+    ControlChar,
+}
+
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// What [`Tokenizer::dedup_whitespace`] does with whitespace-only spans
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WhitespaceMode {
+    /// Replace the span with a single space
+    Collapse,
+    /// Remove the span entirely
+    Drop,
+}
+
+/// Configuration of a [`Tokenizer`], see [`Tokenizer::with_options`]
+///
+/// The default options are those of [`Tokenizer::new`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TokenizerOptions {
+    /// See [`Tokenizer::set_allow_short_close`]
+    pub allow_short_close: bool,
+    /// See [`Tokenizer::set_strict`]
+    pub strict: bool,
+    /// See [`Tokenizer::set_reject_control_chars`]
+    pub reject_control_chars: bool,
+    /// See [`Tokenizer::set_emit_eof`]
+    pub emit_eof: bool,
+    /// Tolerate HTML-style markup, see [`Tokenizer::new_html`]
+    pub html: bool,
+    /// See [`Tokenizer::set_recover`]
+    pub recover: bool,
+    /// Treat `&` as text, see [`TokenizerBuilder::entities`]
+    pub disable_entities: bool,
+    /// Treat `[` and `]` as text, see [`TokenizerBuilder::dtd_subsets`]
+    pub disable_subsets: bool,
 }
 
+/// The part of a [`Tokenizer`] that changes when a token is consumed
+#[derive(Debug, Clone, Copy)]
+struct State<'a> {
+    rest: &'a [u8],
+    dtd_depth: usize,
+    element_depth: usize,
+    eof_emitted: bool,
+    raw_text: Option<&'static [u8]>,
+    ignore_section: bool,
+    subset_start: bool,
+    check_bom: bool,
+    doc_start: bool,
+    error_kind: ErrorKind,
+}
+
+#[derive(Debug, Clone)]
 pub struct Tokenizer<'a> {
+    original: &'a [u8],
     rest: &'a [u8],
-    depth: usize,
+    /// Nesting depth of DTD internal subsets and conditional sections
+    dtd_depth: usize,
+    /// Nesting depth of elements, see [`Tokenizer::depth`]
+    element_depth: usize,
+    sentinel: Option<&'a [u8]>,
+    options: TokenizerOptions,
+    /// Whether the `Token::Eof` has been emitted
+    eof_emitted: bool,
+    /// Name of a raw-text element whose content follows `rest` (HTML only)
+    raw_text: Option<&'static [u8]>,
+    /// Whether `rest` is at the content of an `IGNORE` section
+    ignore_section: bool,
+    /// Whether `rest` starts with the `[` of an internal subset
+    subset_start: bool,
+    /// Whether more input may follow `rest`
+    partial: bool,
+    /// Whether `rest` is at the start of the input, where a BOM may occur
+    check_bom: bool,
+    /// Whether no token but a BOM precedes `rest`, where an XML declaration may occur
+    doc_start: bool,
+    /// Kind of the most recent `Token::Error`
+    error_kind: ErrorKind,
+    /// The next token and the state after it, see [`Tokenizer::peek`]
+    peeked: Option<(Option<Token<'a>>, State<'a>)>,
 }
 
 impl<'a> Tokenizer<'a> {
-    pub fn new(bytes: &'a [u8]) -> Self {
+    /// Create a tokenizer for `bytes` with the default options
+    ///
+    /// This is a `const fn`, so it can be used in `const` and `static` initializers.
+    /// An empty input yields no tokens at all.
+    pub const fn new(bytes: &'a [u8]) -> Self {
         Self {
+            original: bytes,
             rest: bytes,
-            depth: 0,
+            dtd_depth: 0,
+            element_depth: 0,
+            sentinel: None,
+            // `TokenizerOptions::default()`, which is not `const`
+            options: TokenizerOptions {
+                allow_short_close: false,
+                strict: false,
+                reject_control_chars: false,
+                emit_eof: false,
+                html: false,
+                recover: false,
+                disable_entities: false,
+                disable_subsets: false,
+            },
+            eof_emitted: false,
+            raw_text: None,
+            ignore_section: false,
+            subset_start: false,
+            partial: false,
+            check_bom: true,
+            doc_start: true,
+            error_kind: ErrorKind::UnterminatedTag,
+            peeked: None,
+        }
+    }
+
+    /// Create a tokenizer that skips a UTF-8 Byte Order Mark at the start of `bytes`
+    ///
+    /// Unlike [`Tokenizer::new`], this never yields a `Token::Bom`. Offsets still
+    /// count the skipped bytes.
+    pub fn new_skip_bom(bytes: &'a [u8]) -> Self {
+        Self {
+            rest: bytes.strip_prefix(BOM).unwrap_or(bytes),
+            check_bom: false,
+            ..Self::new(bytes)
+        }
+    }
+
+    /// Create a tokenizer that stops at the first occurrence of `sentinel` in content
+    ///
+    /// Once the sentinel is reached, the iterator returns `None` and the sentinel
+    /// and all bytes following it are left in [`Tokenizer::remaining`]. The sentinel
+    /// is only looked for outside of markup, i.e. not within tags or comments.
+    pub fn until_sentinel(bytes: &'a [u8], sentinel: &'a [u8]) -> Self {
+        Self {
+            sentinel: (!sentinel.is_empty()).then_some(sentinel),
+            ..Self::new(bytes)
+        }
+    }
+
+    /// Create a tokenizer that is lenient towards common HTML markup
+    ///
+    /// In addition to `--!>` comment endings (see [`Tokenizer::set_allow_short_close`]),
+    /// this accepts lowercase declarations like `<!doctype html>` and emits a
+    /// `<` or `&` that does not start markup or a complete reference as a
+    /// one-byte `Span`. The content of `<script>` and `<style>` elements is
+    /// returned as a single `Span`, up to the matching end tag. A bogus comment
+    /// like `<!>` or `<!1 x>` is emitted as a `Comment` up to the next `>`.
+    pub fn new_html(bytes: &'a [u8]) -> Self {
+        Self::new(bytes).with_options(TokenizerOptions {
+            allow_short_close: true,
+            html: true,
+            ..TokenizerOptions::default()
+        })
+    }
+
+    /// Create a tokenizer for a buffer that may be followed by more input
+    ///
+    /// When a token would run off the end of `bytes` (e.g. a `<` or `<a x='1`
+    /// without the closing `>`, or a trailing text `Span`), the iterator returns
+    /// `None` without consuming it. These bytes are left in [`Tokenizer::remaining`],
+    /// so that they can be tokenized again once the next chunk has been appended:
+    ///
+    /// ```
+    /// use xtoken::{TokenKind, Tokenizer};
+    ///
+    /// let chunks: [&[u8]; 3] = [b"<a x='1", b"'>text &am", b"p;</a>"];
+    /// let mut buf = Vec::new();
+    /// let mut kinds = Vec::new();
+    /// for chunk in chunks {
+    ///     buf.extend_from_slice(chunk);
+    ///     let mut tokens = Tokenizer::new_partial(&buf);
+    ///     kinds.extend(tokens.by_ref().map(|token| token.kind()));
+    ///     let consumed = buf.len() - tokens.remaining().len();
+    ///     buf.drain(..consumed);
+    /// }
+    /// // at the end of the input, the rest is tokenized as usual
+    /// kinds.extend(Tokenizer::new(&buf).map(|token| token.kind()));
+    /// assert_eq!(kinds, [TokenKind::Element, TokenKind::Span, TokenKind::Entity, TokenKind::ElementEnd]);
+    /// ```
+    ///
+    /// Offsets are relative to `bytes`. The nesting of DTD internal subsets,
+    /// the content of an `IGNORE` section, the raw text of an HTML `<script>`
+    /// or `<style>` element and whether the XML declaration may still follow
+    /// are not carried over to the next buffer.
+    /// `ReaderTokenizer` (with the `io` feature) drives this loop over a fixed
+    /// buffer and keeps that state.
+    pub fn new_partial(bytes: &'a [u8]) -> Self {
+        Self {
+            partial: true,
+            ..Self::new(bytes)
+        }
+    }
+
+    /// Replace the configuration of this tokenizer, keeping its position
+    ///
+    /// This can be used to change the options in the middle of the input,
+    /// e.g. to continue in a more lenient mode after an error.
+    pub fn with_options(self, options: TokenizerOptions) -> Self {
+        Self {
+            options,
+            peeked: None,
+            ..self
+        }
+    }
+
+    /// The current configuration of this tokenizer
+    pub fn options(&self) -> TokenizerOptions {
+        self.options
+    }
+
+    /// Accept `--!>` in addition to `-->` as the end of a comment
+    ///
+    /// This is not valid XML, but HTML parsers treat it as an "abrupt closing"
+    /// of the comment. When disabled (the default), such a comment continues
+    /// until the next `-->`.
+    pub fn set_allow_short_close(&mut self, allow: bool) {
+        self.options.allow_short_close = allow;
+        self.peeked = None;
+    }
+
+    /// Enable additional well-formedness checks (disabled by default)
+    ///
+    /// In strict mode, the names of entity references (`&name;`) must be
+    /// valid XML names and processing instructions must not use targets that
+    /// are reserved for XML, i.e. which start with `xml` in any case. Comments
+    /// must not contain `--`, in which case the whole comment is an error.
+    /// A `]]>` in text is emitted as a three-byte `Token::Error`.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.options.strict = strict;
+        self.peeked = None;
+    }
+
+    /// Report C0 control characters in text as errors (disabled by default)
+    ///
+    /// XML 1.0 only allows tab, line feed and carriage return. When enabled,
+    /// each other byte below `0x20` in a `Span` is emitted as a one-byte
+    /// `Token::Error` instead. This needs an additional pass over all text.
+    pub fn set_reject_control_chars(&mut self, reject: bool) {
+        self.options.reject_control_chars = reject;
+        self.peeked = None;
+    }
+
+    /// Continue after an unterminated token instead of ending in an error (disabled by default)
+    ///
+    /// By default, a token that is never terminated (e.g. `<a x='`) turns the
+    /// whole rest of the input into a single `Token::Error`. When enabled, the
+    /// error only extends to the next `<` or `&` and tokenizing resumes there.
+    pub fn set_recover(&mut self, recover: bool) {
+        self.options.recover = recover;
+        self.peeked = None;
+    }
+
+    /// Emit a single `Token::Eof` after the last token, before returning `None`
+    ///
+    /// This is disabled by default. Note that `Eof` is an exception to the rule
+    /// that all tokens are non-empty.
+    pub fn set_emit_eof(&mut self, emit_eof: bool) {
+        self.options.emit_eof = emit_eof;
+        self.peeked = None;
+    }
+
+    /// The number of elements that have been opened but not closed yet
+    ///
+    /// This counts `Element` tokens and subtracts `ElementEnd` tokens, without
+    /// checking that their names match. A stray end tag at depth zero leaves the
+    /// depth at zero. `EmptyElement` tokens do not change the depth.
+    ///
+    /// This is separate from the nesting of DTD internal subsets, which only
+    /// decides whether `]` is treated as markup.
+    pub fn depth(&self) -> usize {
+        self.element_depth
+    }
+
+    /// The input that has not been tokenized yet
+    pub fn remaining(&self) -> &'a [u8] {
+        self.rest
+    }
+
+    /// Continue with tokenizing `bytes` from the start, keeping the configuration
+    ///
+    /// This resets the nesting depths and all other state, so that the tokenizer
+    /// behaves like a new one with the same options and sentinel.
+    pub fn reset(&mut self, bytes: &'a [u8]) {
+        *self = Self {
+            options: self.options,
+            sentinel: self.sentinel,
+            partial: self.partial,
+            ..Self::new(bytes)
+        };
+    }
+
+    /// Continue tokenizing at byte `offset` of the input
+    ///
+    /// To know whether `offset` is within a DTD subset, the input is tokenized
+    /// from the start up to `offset`, so this takes time linear in `offset`,
+    /// regardless of the current position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset` is greater than the length of the input.
+    pub fn scan_to_byte_offset(&mut self, offset: usize) {
+        assert!(
+            offset <= self.original.len(),
+            "offset {} out of range for input of length {}",
+            offset,
+            self.original.len()
+        );
+        let mut scan = Self {
+            rest: self.original,
+            dtd_depth: 0,
+            element_depth: 0,
+            sentinel: None,
+            eof_emitted: true,
+            partial: false,
+            check_bom: true,
+            doc_start: true,
+            raw_text: None,
+            ignore_section: false,
+            subset_start: false,
+            peeked: None,
+            ..self.clone()
+        };
+        while scan.offset() < offset && scan.next().is_some() {}
+        self.rest = &self.original[offset..];
+        self.dtd_depth = scan.dtd_depth;
+        self.element_depth = scan.element_depth;
+        self.raw_text = scan.raw_text.filter(|_| scan.offset() == offset);
+        self.ignore_section = scan.ignore_section && scan.offset() == offset;
+        self.subset_start = scan.subset_start && scan.offset() == offset;
+        self.check_bom = offset == 0;
+        self.doc_start = scan.doc_start && scan.offset() == offset;
+        self.peeked = None;
+    }
+
+    /// Consume raw text up to (but not including) the end tag `</name>`
+    ///
+    /// The content is treated as opaque bytes, i.e. no markup or entities are
+    /// recognized, which is what raw-text elements like `<script>` need.
+    /// If there is no matching end tag, the whole rest of the input is returned.
+    pub fn scan_raw_until_close_tag(&mut self, name: &[u8]) -> &'a [u8] {
+        self.scan_raw(name, false)
+    }
+
+    /// Like [`Tokenizer::scan_raw_until_close_tag`], but matches `name` ASCII case-insensitively
+    pub fn scan_raw_until_close_tag_ignore_case(&mut self, name: &[u8]) -> &'a [u8] {
+        self.scan_raw(name, true)
+    }
+
+    /// Pair each token with the element nesting depth at which it occurs
+    ///
+    /// Start tags report the depth *after* they are opened and end tags the
+    /// depth *before* they are closed, so both tags of an element share the
+    /// depth of its content. A self-closing element (`<x/>`) reports the depth
+    /// it would have as a start tag, without changing the depth that follows.
+    /// Counting starts at [`Tokenizer::depth`], so the depths are also correct
+    /// for a tokenizer that is already inside of an element.
+    pub fn with_depth_info(self) -> impl Iterator<Item = (Token<'a>, usize)> {
+        let depth = self.element_depth;
+        self.scan(depth, |depth, token| {
+            let current = match token {
+                Token::EmptyElement(_) => *depth + 1,
+                Token::Element(_) => {
+                    *depth += 1;
+                    *depth
+                }
+                Token::ElementEnd(_) => {
+                    let current = *depth;
+                    *depth = depth.saturating_sub(1);
+                    current
+                }
+                _ => *depth,
+            };
+            Some((token, current))
+        })
+    }
+
+    /// Iterator over the tokens outside of the root element, and the root element's tags
+    ///
+    /// This skips everything that is nested within an element, which leaves the
+    /// prolog, the start and end tag of the root element and the epilog.
+    /// Counting starts at [`Tokenizer::depth`], so for a tokenizer inside of an
+    /// element, the nested content is skipped up to the root element's end tag.
+    pub fn top_level(self) -> impl Iterator<Item = Token<'a>> {
+        let depth = self.element_depth;
+        self.scan(depth, |depth, token| {
+            let before = *depth;
+            match token {
+                Token::Element(_) => *depth += 1,
+                Token::ElementEnd(_) => *depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            Some((token, before.min(*depth)))
+        })
+        .filter_map(|(token, depth)| (depth == 0).then_some(token))
+    }
+
+    /// Iterator over the name and raw text content of all elements without child elements
+    ///
+    /// The text is not decoded and may contain entities and CDATA sections.
+    /// Elements with comments or processing instructions in their content and
+    /// self-closing elements are skipped.
+    pub fn leaf_text(mut self) -> impl Iterator<Item = (&'a [u8], &'a [u8])> {
+        let original = self.original;
+        let mut leaf = None;
+        core::iter::from_fn(move || loop {
+            let (range, token) = self.next_spanned()?;
+            match token {
+                Token::Element(span) => leaf = tag::element_name(span).map(|n| (n, range.end)),
+                Token::Span(_) | Token::Entity(_) | Token::CData(_) => {}
+                Token::ElementEnd(span) => {
+                    if let Some((name, start)) = leaf.take() {
+                        if tag::element_name(span) == Some(name) {
+                            return Some((name, &original[start..range.start]));
+                        }
+                    }
+                }
+                _ => leaf = None,
+            }
+        })
+    }
+
+    /// Pair each token with its byte range and, for spans, the range of its trimmed content
+    ///
+    /// The trimmed range excludes leading and trailing whitespace. It is empty
+    /// (positioned at the end of the span) for whitespace-only spans and `None`
+    /// for all tokens other than `Span`.
+    pub fn tokens_with_raw_ranges(
+        mut self,
+    ) -> impl Iterator<Item = (Token<'a>, Range<usize>, Option<Range<usize>>)> {
+        core::iter::from_fn(move || {
+            let start = self.offset();
+            let token = self.next()?;
+            let end = self.offset();
+            let trimmed = match token {
+                Token::Span(span) => {
+                    let lead = span.iter().take_while(|&&b| tag::is_space(b)).count();
+                    let trail = span[lead..]
+                        .iter()
+                        .rev()
+                        .take_while(|&&b| tag::is_space(b))
+                        .count();
+                    Some((start + lead)..(end - trail))
+                }
+                _ => None,
+            };
+            Some((token, start..end, trimmed))
+        })
+    }
+
+    /// Iterator over all start and self-closing elements whose name satisfies `pred`
+    pub fn elements_named(self, pred: impl Fn(&[u8]) -> bool) -> impl Iterator<Item = Token<'a>> {
+        self.filter(move |token| {
+            matches!(token, Token::Element(span) | Token::EmptyElement(span) if tag::element_name(span).is_some_and(&pred))
+        })
+    }
+
+    /// Pair each token with the line and column at which it starts
+    pub fn line_col(self) -> LineCol<'a> {
+        LineCol::new(self)
+    }
+
+    /// Turn this tokenizer into an iterator of [`Event`]s
+    pub fn events(self) -> Events<'a> {
+        Events::new(self)
+    }
+
+    /// Advance to the `<!DOCTYPE ...>` declaration and return it
+    ///
+    /// Returns `None` if there is no doctype before the root element. In that
+    /// case, the tokenizer stops right before the root element. In html mode,
+    /// the keyword is matched case-insensitively, e.g. `<!doctype html>`.
+    pub fn find_doctype(&mut self) -> Option<Token<'a>> {
+        loop {
+            let mut next = self.clone();
+            match next.next()? {
+                Token::Element(_) | Token::EmptyElement(_) => return None,
+                token => {
+                    *self = next;
+                    if self.is_doctype(&token) {
+                        return Some(token);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Check whether `token` is a doctype, ignoring the case of the keyword in html mode
+    fn is_doctype(&self, token: &Token<'_>) -> bool {
+        match token {
+            Token::Decl(decl) => dtd::is_doctype(decl, self.options.html),
+            _ => false,
+        }
+    }
+
+    /// Skip the `<!DOCTYPE ...>` declaration if it is the next markup
+    ///
+    /// This consumes the whole declaration, including an internal subset
+    /// (`[ ... ]>`), and any whitespace before it. If the next markup is not a
+    /// doctype, the tokenizer is left unchanged. In html mode, the keyword is
+    /// matched case-insensitively, e.g. `<!doctype html>`.
+    pub fn skip_doctype(&mut self) {
+        let mut next = self.clone();
+        let dtd_depth = next.dtd_depth;
+        if !next.next_nonspace().is_some_and(|t| self.is_doctype(&t)) {
+            return;
+        }
+        while (next.subset_start || next.dtd_depth > dtd_depth) && next.next().is_some() {}
+        *self = next;
+    }
+
+    /// Iterator over the targets of all processing instructions
+    pub fn pi_targets(self) -> impl Iterator<Item = &'a [u8]> {
+        self.filter_map(|token| token.pi_target())
+    }
+
+    /// The next token, without advancing the tokenizer
+    ///
+    /// The token is cached, so that the following call to `next` (or `peek`)
+    /// does not scan it again. Until then, [`Tokenizer::remaining`] and the
+    /// depths still refer to the position before the token.
+    pub fn peek(&mut self) -> Option<Token<'a>> {
+        if let Some((token, _)) = &self.peeked {
+            return token.clone();
+        }
+        let before = self.state();
+        let token = self.next();
+        let after = self.state();
+        self.set_state(before);
+        self.peeked = Some((token.clone(), after));
+        token
+    }
+
+    fn state(&self) -> State<'a> {
+        State {
+            rest: self.rest,
+            dtd_depth: self.dtd_depth,
+            element_depth: self.element_depth,
+            eof_emitted: self.eof_emitted,
+            raw_text: self.raw_text,
+            ignore_section: self.ignore_section,
+            subset_start: self.subset_start,
+            check_bom: self.check_bom,
+            doc_start: self.doc_start,
+            error_kind: self.error_kind,
+        }
+    }
+
+    fn set_state(&mut self, state: State<'a>) {
+        self.rest = state.rest;
+        self.dtd_depth = state.dtd_depth;
+        self.element_depth = state.element_depth;
+        self.eof_emitted = state.eof_emitted;
+        self.raw_text = state.raw_text;
+        self.ignore_section = state.ignore_section;
+        self.subset_start = state.subset_start;
+        self.check_bom = state.check_bom;
+        self.doc_start = state.doc_start;
+        self.error_kind = state.error_kind;
+    }
+
+    /// The kind of the next token, without advancing the tokenizer
+    pub fn peek_kind(&self) -> Option<TokenKind> {
+        self.clone().next().map(|token| token.kind())
+    }
+
+    /// Check whether the next token is of the given kind, without advancing the tokenizer
+    pub fn peek_is(&self, kind: TokenKind) -> bool {
+        self.peek_kind() == Some(kind)
+    }
+
+    /// Return the next token that is not a whitespace-only `Span`
+    ///
+    /// Any whitespace spans before that token are consumed.
+    pub fn next_nonspace(&mut self) -> Option<Token<'a>> {
+        self.find(
+            |token| !matches!(token, Token::Span(span) if span.iter().all(|&b| tag::is_space(b))),
+        )
+    }
+
+    /// Normalize whitespace-only spans according to `mode`
+    ///
+    /// All other tokens are passed through unchanged. Note that collapsed spans
+    /// (`Token::Span(b" ")`) are not part of the input, so the tokens no longer
+    /// partition it.
+    pub fn dedup_whitespace(self, mode: WhitespaceMode) -> impl Iterator<Item = Token<'a>> {
+        self.filter_map(move |token| match token {
+            token if token.is_whitespace() => match mode {
+                WhitespaceMode::Collapse => Some(Token::Span(b" ")),
+                WhitespaceMode::Drop => None,
+            },
+            token => Some(token),
+        })
+    }
+
+    /// Convert each token to a [`StrToken`], checking that its bytes are valid UTF-8
+    ///
+    /// A token that is not valid UTF-8 is returned as `StrToken::Invalid`,
+    /// so that an encoding error only affects the token that contains it.
+    pub fn str_tokens(self) -> impl Iterator<Item = StrToken<'a>> {
+        self.map(StrToken::from)
+    }
+
+    /// Merge each run of consecutive `Span` and `Entity` tokens into a [`TextEvent`]
+    ///
+    /// All other tokens are passed through unchanged, so a run of text ends at
+    /// the first token that is not text, e.g. a comment or an error.
+    pub fn text_events(mut self) -> impl Iterator<Item = MergedToken<'a>> {
+        core::iter::from_fn(move || text::next_merged(&mut self))
+    }
+
+    /// Count the remaining tokens by kind
+    pub fn stats(self) -> TokenStats {
+        let mut stats = TokenStats::default();
+        for token in self {
+            stats.add(token.kind());
+        }
+        stats
+    }
+
+    /// Iterator over the bytes of all `Token::Error`s in the rest of the input
+    pub fn errors(self) -> impl Iterator<Item = &'a [u8]> {
+        self.filter_map(|token| match token {
+            Token::Error(span) => Some(span),
+            _ => None,
+        })
+    }
+
+    /// Skip whitespace-only spans, see [`Token::is_whitespace`]
+    pub fn significant(self) -> impl Iterator<Item = Token<'a>> {
+        self.filter(|token| !token.is_whitespace())
+    }
+
+    /// Collect all remaining tokens into a `Vec`, pre-sized from [`Iterator::size_hint`]
+    ///
+    /// The upper bound of one token per byte is reserved, but at most 1024
+    /// tokens, so that large documents do not allocate far more than they need.
+    #[cfg(feature = "alloc")]
+    pub fn tokens_vec(self) -> alloc::vec::Vec<Token<'a>> {
+        const MAX_RESERVE: usize = 1024;
+        let (lower, upper) = self.size_hint();
+        let capacity = upper.map_or(lower, |upper| upper.min(MAX_RESERVE).max(lower));
+        let mut tokens = alloc::vec::Vec::with_capacity(capacity);
+        tokens.extend(self);
+        tokens
+    }
+
+    /// Rewrite the names of start, end and empty element tags with `f`
+    ///
+    /// Each token is paired with its bytes, where the name of the tag is
+    /// replaced by the result of `f`. The bytes are borrowed from the input
+    /// if `f` returns the name unchanged, and allocated otherwise.
+    #[cfg(feature = "alloc")]
+    pub fn map_names<F>(self, f: F) -> impl Iterator<Item = (Token<'a>, Cow<'a, [u8]>)>
+    where
+        F: Fn(&'a [u8]) -> Cow<'a, [u8]>,
+    {
+        self.map(move |token| {
+            let bytes = token.as_bytes();
+            let name = match token {
+                Token::Element(span) | Token::EmptyElement(span) | Token::ElementEnd(span) => {
+                    tag::element_name(span)
+                }
+                _ => None,
+            };
+            let Some(name) = name else {
+                return (token, Cow::Borrowed(bytes));
+            };
+            match f(name) {
+                Cow::Borrowed(new) if new == name => (token, Cow::Borrowed(bytes)),
+                new => {
+                    let start = name.as_ptr() as usize - bytes.as_ptr() as usize;
+                    let mut mapped =
+                        alloc::vec::Vec::with_capacity(bytes.len() - name.len() + new.len());
+                    mapped.extend_from_slice(&bytes[..start]);
+                    mapped.extend_from_slice(&new);
+                    mapped.extend_from_slice(&bytes[(start + name.len())..]);
+                    (token, Cow::Owned(mapped))
+                }
+            }
+        })
+    }
+
+    /// Pair each token with its byte range, as an [`OwnedToken`] that does not borrow the input
+    #[cfg(feature = "alloc")]
+    pub fn spanned_owned(mut self) -> impl Iterator<Item = (Range<usize>, OwnedToken)> + 'a {
+        core::iter::from_fn(move || {
+            let (range, token) = self.next_spanned()?;
+            Some((range, OwnedToken::from(token)))
+        })
+    }
+
+    /// Tokenize the rest of the input and collect all `Token::Error` spans with their byte offsets
+    #[cfg(feature = "alloc")]
+    pub fn collect_errors(mut self) -> alloc::vec::Vec<(usize, &'a [u8])> {
+        let mut errors = alloc::vec::Vec::new();
+        loop {
+            let offset = self.offset();
+            match self.next() {
+                Some(Token::Error(span)) => errors.push((offset, span)),
+                Some(_) => {}
+                None => break errors,
+            }
+        }
+    }
+
+    /// Write the decoded text up to the next markup into `w`
+    ///
+    /// This consumes consecutive `Span` and `Entity` tokens, resolving the
+    /// predefined entities and character references, and stops before the next
+    /// `<` (or `]` within a DTD subset), a sentinel or, in partial mode, an
+    /// incomplete token. On error, the offending token has already been consumed.
+    pub fn write_decoded_text(&mut self, w: &mut impl core::fmt::Write) -> Result<(), DecodeError> {
+        while let Some(&first) = self.rest.first() {
+            if first == b'<' || (first == b']' && self.dtd_depth > 0) {
+                break;
+            }
+            match self.next() {
+                Some(Token::Span(span)) => {
+                    w.write_str(core::str::from_utf8(span).map_err(|_| DecodeError::Utf8)?)?
+                }
+                Some(Token::Entity(entity)) => {
+                    w.write_char(entity::decode_reference(entity).ok_or(DecodeError::Entity)?)?
+                }
+                Some(Token::Error(span)) if span.starts_with(b"&") => {
+                    return Err(DecodeError::Entity)
+                }
+                Some(Token::Bom(_)) => {}
+                Some(_) => return Err(DecodeError::Malformed),
+                None => break,
+            }
+        }
+        Ok(())
+    }
+
+    fn scan_raw(&mut self, name: &[u8], ignore_case: bool) -> &'a [u8] {
+        self.peeked = None;
+        let mut offset = 0;
+        let mid = loop {
+            if let Some(pos) = memchr::memmem::find(&self.rest[offset..], b"</") {
+                let start = offset + pos;
+                let tail = &self.rest[(start + 2)..];
+                if tail.len() >= name.len() {
+                    let (candidate, after) = tail.split_at(name.len());
+                    let is_match = match ignore_case {
+                        true => candidate.eq_ignore_ascii_case(name),
+                        false => candidate == name,
+                    };
+                    if is_match
+                        && matches!(
+                            after.first(),
+                            None | Some(b'>' | b' ' | b'\t' | b'\r' | b'\n')
+                        )
+                    {
+                        break start;
+                    }
+                }
+                offset = start + 2;
+            } else {
+                break self.rest.len();
+            }
+        };
+        let (raw, rest) = self.rest.split_at(mid);
+        self.rest = rest;
+        raw
+    }
+
+    /// The number of bytes of the input that have been consumed
+    ///
+    /// This is the offset at which the next token starts.
+    pub fn offset(&self) -> usize {
+        self.original.len() - self.rest.len()
+    }
+
+    /// Like [`Iterator::next`], but pairs the token with its byte range in the input
+    pub fn next_spanned(&mut self) -> Option<(Range<usize>, Token<'a>)> {
+        let start = self.offset();
+        let token = self.next()?;
+        Some((start..self.offset(), token))
+    }
+
+    /// Like [`Iterator::next`], but returns `None` without advancing if `should_stop()` is true
+    ///
+    /// This allows a long-running loop to be interrupted, with the input that
+    /// was not tokenized still available from [`Tokenizer::remaining`].
+    pub fn next_cancellable(&mut self, should_stop: impl Fn() -> bool) -> Option<Token<'a>> {
+        if should_stop() {
+            return None;
+        }
+        self.next()
+    }
+
+    /// Like [`Iterator::next`], but reports malformed tokens as an error
+    ///
+    /// The error carries the [`ErrorKind`] and the byte offset at which the
+    /// malformed token starts in the input.
+    pub fn checked_next(&mut self) -> Option<Result<Token<'a>, (ErrorKind, usize)>> {
+        let offset = self.offset();
+        match self.next()? {
+            Token::Error(_) => Some(Err((self.error_kind, offset))),
+            token => Some(Ok(token)),
+        }
+    }
+
+    /// Tokenize `bytes` to completion and report all `Token::Error`s
+    ///
+    /// This uses the default options. Returns `Ok(())` if `bytes` can be
+    /// tokenized without errors, which does not imply that they are well-formed.
+    pub fn validate(bytes: &[u8]) -> Result<(), ValidationReport> {
+        validate::validate(Tokenizer::new(bytes))
+    }
+
+    fn span(&mut self, mut len: usize) -> Token<'a> {
+        if let Some(sentinel) = self.sentinel {
+            let end = self.rest.len().min(len + sentinel.len() - 1);
+            if let Some(pos) = memchr::memmem::find(&self.rest[..end], sentinel) {
+                len = len.min(pos);
+            }
+        }
+        if self.options.strict && self.dtd_depth == 0 {
+            match memchr::memmem::find(&self.rest[..len], b"]]>") {
+                Some(0) => {
+                    let (span, rest) = self.rest.split_at(3);
+                    self.rest = rest;
+                    self.error_kind = ErrorKind::CDataEndInText;
+                    return Token::Error(span);
+                }
+                Some(pos) => len = pos,
+                None => {}
+            }
+        }
+        if self.options.reject_control_chars {
+            let is_control = |b: u8| b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r');
+            match self.rest[..len].iter().position(|&b| is_control(b)) {
+                Some(0) => {
+                    let (span, rest) = self.rest.split_at(1);
+                    self.rest = rest;
+                    self.error_kind = ErrorKind::ControlChar;
+                    return Token::Error(span);
+                }
+                Some(pos) => len = pos,
+                None => {}
+            }
+        }
+        let (span, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        Token::Span(span)
+    }
+
+    fn rest_err(&mut self, kind: ErrorKind) -> Token<'a> {
+        self.error_kind = kind;
+        if self.partial {
+            // the token may be completed by more input, see `next`
+            return Token::Error(&self.rest[..0]);
+        }
+        let mut len = self.rest.len();
+        if self.options.recover {
+            if let Some(pos) = memchr::memchr2(b'<', b'&', &self.rest[1..]) {
+                len = pos + 1;
+            }
         }
+        let (span, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        Token::Error(span)
     }
 
-    fn rest_err(&mut self) -> Token<'a> {
-        let (span, rest) = self.rest.split_at(self.rest.len());
+    /// Error for a malformed tag, up to the next `>` or before the next `<`
+    ///
+    /// Unlike `rest_err`, this keeps the structure after the tag.
+    fn tag_err(&mut self, kind: ErrorKind) -> Token<'a> {
+        self.error_kind = kind;
+        let len = match memchr::memchr2(b'>', b'<', &self.rest[1..]) {
+            Some(pos) if self.rest[pos + 1] == b'>' => pos + 2,
+            Some(pos) => pos + 1,
+            // the tag may be continued by more input
+            None if self.partial => return Token::Error(&self.rest[..0]),
+            None => self.rest.len(),
+        };
+        let (span, rest) = self.rest.split_at(len);
         self.rest = rest;
         Token::Error(span)
     }
@@ -77,13 +1315,22 @@ impl<'a> Tokenizer<'a> {
                     if chr2 == b'>' {
                         let span = &self.rest[..(self.rest.len() - rest2.len())];
                         self.rest = rest2;
+                        if self.options.strict && pi::has_reserved_target(span) {
+                            self.error_kind = ErrorKind::ReservedPiTarget;
+                            break Token::Error(span);
+                        }
+                        if self.doc_start
+                            && pi::pi_target_data(span).is_some_and(|(t, _)| t == b"xml")
+                        {
+                            break Token::XmlDecl(span);
+                        }
                         break Token::PI(span);
                     }
                 } else {
-                    break self.rest_err();
+                    break self.rest_err(ErrorKind::UnterminatedPi);
                 }
             } else {
-                break self.rest_err();
+                break self.rest_err(ErrorKind::UnterminatedPi);
             }
         }
     }
@@ -99,84 +1346,200 @@ impl<'a> Tokenizer<'a> {
                             let (span, rest) = self.rest.split_at(mid);
                             self.rest = rest;
                             break Token::Comment(span);
+                        } else if self.options.allow_short_close && rest2.starts_with(b"!>") {
+                            let mid = self.rest.len() - (rest2.len() - 2);
+                            let (span, rest) = self.rest.split_at(mid);
+                            self.rest = rest;
+                            break Token::Comment(span);
+                        } else if self.options.strict {
+                            // the end may overlap with this `--`, as in `--->`
+                            let Some(pos) = memchr::memmem::find(rest, b"-->") else {
+                                break self.rest_err(ErrorKind::UnterminatedComment);
+                            };
+                            let mid = self.rest.len() - (rest.len() - (pos + 3));
+                            let (span, rest) = self.rest.split_at(mid);
+                            self.rest = rest;
+                            self.error_kind = ErrorKind::DoubleHyphenInComment;
+                            break Token::Error(span);
                         } else {
                             // technically invalid, but ignore
                         }
                     }
                 } else {
-                    break self.rest_err();
+                    break self.rest_err(ErrorKind::UnterminatedComment);
                 }
             } else {
-                break self.rest_err();
+                break self.rest_err(ErrorKind::UnterminatedComment);
             }
         }
     }
 
-    fn decl(&mut self, rest: &'a [u8]) -> Token<'a> {
-        if let Some(pos) = memchr::memchr2(b'>', b'[', rest) {
-            let mid = self.rest.len() - (rest.len() - (pos + 1));
-            let (span, rest) = self.rest.split_at(mid);
+    /// A `<!` that is not followed by markup, up to the next `>` (HTML only)
+    fn bogus_comment(&mut self) -> Token<'a> {
+        if let Some(pos) = memchr::memchr(b'>', self.rest) {
+            let (span, rest) = self.rest.split_at(pos + 1);
             self.rest = rest;
-            if span[mid - 1] == b'[' {
-                self.depth += 1;
-            }
-            Token::Decl(span)
+            Token::Comment(span)
         } else {
-            self.rest_err()
+            self.rest_err(ErrorKind::UnterminatedComment)
         }
     }
 
-    fn decl_end(&mut self) -> Token<'a> {
+    fn cdata(&mut self, rest: &'a [u8]) -> Token<'a> {
+        if let Some(pos) = memchr::memmem::find(rest, b"]]>") {
+            let mid = self.rest.len() - (rest.len() - (pos + 3));
+            let (span, rest) = self.rest.split_at(mid);
+            self.rest = rest;
+            Token::CData(span)
+        } else {
+            self.rest_err(ErrorKind::UnterminatedCData)
+        }
+    }
+
+    fn decl(&mut self, rest: &'a [u8]) -> Token<'a> {
+        if let Some(pos) = tag::find_decl_end(rest) {
+            let mid = self.rest.len() - (rest.len() - (pos + 1));
+            // the `[` of an internal subset is a separate token
+            self.subset_start = self.rest[mid - 1] == b'[' && !self.options.disable_subsets;
+            let (span, rest) = self.rest.split_at(mid - usize::from(self.subset_start));
+            self.rest = rest;
+            Token::Decl(span)
+        } else {
+            self.rest_err(ErrorKind::UnterminatedDecl)
+        }
+    }
+
+    fn decl_end(&mut self) -> Token<'a> {
+        if self.rest.starts_with(b"]]>") {
+            let (span, rest) = self.rest.split_at(3);
+            self.rest = rest;
+            self.dtd_depth = self.dtd_depth.saturating_sub(1);
+            return Token::CondClose(span);
+        }
         if let Some(pos) = memchr::memchr(b'>', self.rest) {
             let (span, rest) = self.rest.split_at(pos + 1);
             self.rest = rest;
-            self.depth -= 1;
+            self.dtd_depth = self.dtd_depth.saturating_sub(1);
             Token::DeclEnd(span)
         } else {
-            self.rest_err()
+            self.rest_err(ErrorKind::UnterminatedDecl)
+        }
+    }
+
+    fn cond_open(&mut self, rest: &'a [u8]) -> Token<'a> {
+        let Some(pos) = memchr::memchr(b'[', rest) else {
+            return self.rest_err(ErrorKind::UnterminatedDecl);
+        };
+        let keyword = tag::trim_end(tag::trim_start(&rest[..pos]));
+        let is_keyword = match keyword {
+            b"INCLUDE" | b"IGNORE" => true,
+            // parameter entity reference, resolved by the consumer
+            [b'%', name @ .., b';'] => tag::is_name(name),
+            _ => false,
+        };
+        if !is_keyword {
+            return self.tag_err(ErrorKind::InvalidCondSection);
         }
+        let mid = self.rest.len() - (rest.len() - (pos + 1));
+        let (span, rest) = self.rest.split_at(mid);
+        self.rest = rest;
+        self.dtd_depth += 1;
+        self.ignore_section = keyword == b"IGNORE";
+        Token::CondOpen(span)
     }
 
     fn builtin(&mut self, rest: &'a [u8]) -> Token<'a> {
         if rest.starts_with(b"--") {
             self.comment(&rest[2..])
+        } else if rest.starts_with(b"[CDATA[") {
+            self.cdata(&rest[7..])
         } else {
             match rest.first().copied() {
                 Some(b'A'..=b'Z') => self.decl(rest),
-                None => self.rest_err(),
-                _ => todo!(),
+                Some(b'a'..=b'z') if self.options.html => self.decl(rest),
+                Some(b'[') => self.cond_open(&rest[1..]),
+                None => self.rest_err(ErrorKind::UnterminatedDecl),
+                Some(b'-') if rest.len() == 1 => self.rest_err(ErrorKind::UnterminatedComment),
+                _ if self.options.html => self.bogus_comment(),
+                _ => self.tag_err(ErrorKind::InvalidDecl),
             }
         }
     }
 
     fn entity(&mut self) -> Token<'a> {
-        // entity
-        if let Some(pos) = memchr::memchr(b';', self.rest) {
+        if self.options.html && !entity::is_reference(self.rest) {
+            // in partial mode, the reference may be completed by more input
+            let may_continue = self.rest[1..]
+                .iter()
+                .all(|&b| !matches!(b, b';' | b'<' | b'&') && !tag::is_space(b));
+            if !(self.partial && may_continue) {
+                return self.span(1);
+            }
+        }
+        // entity, which can't contain further markup
+        let end = memchr::memchr3(b';', b'<', b'&', &self.rest[1..]).map(|pos| pos + 1);
+        if let Some(pos) = end.filter(|&pos| self.rest[pos] == b';') {
             let (span, rest) = self.rest.split_at(pos + 1);
             self.rest = rest;
+            let name = &span[1..pos];
+            if self.options.strict && !name.starts_with(b"#") && !tag::is_name(name) {
+                self.error_kind = ErrorKind::InvalidEntityName;
+                return Token::Error(span);
+            }
             Token::Entity(span)
+        } else if end.is_none() && self.partial {
+            // the `;` may follow in more input
+            self.rest_err(ErrorKind::UnterminatedEntity)
         } else {
-            self.rest_err()
+            // the name of the reference, if any, ends at whitespace or markup
+            let len = self.rest[1..]
+                .iter()
+                .position(|&b| matches!(b, b'<' | b'&') || tag::is_space(b))
+                .map_or(self.rest.len(), |pos| pos + 1);
+            let (span, rest) = self.rest.split_at(len);
+            self.rest = rest;
+            self.error_kind = ErrorKind::UnterminatedEntity;
+            Token::Error(span)
         }
     }
 
     fn element(&mut self) -> Token<'a> {
-        if let Some(pos) = memchr::memchr(b'>', self.rest) {
+        if !tag::is_name_start_char(self.rest[1]) {
+            return self.tag_err(ErrorKind::InvalidTagStart);
+        }
+        if let Some(pos) = tag::find_tag_end(self.rest) {
             let (span, rest) = self.rest.split_at(pos + 1);
             self.rest = rest;
+            if span[pos - 1] == b'/' {
+                return Token::EmptyElement(span);
+            }
+            if self.options.html {
+                self.raw_text = tag::element_name(span).and_then(|name| {
+                    [&b"script"[..], b"style"]
+                        .into_iter()
+                        .find(|raw| name.eq_ignore_ascii_case(raw))
+                });
+            }
             Token::Element(span)
         } else {
-            self.rest_err()
+            self.rest_err(ErrorKind::UnterminatedTag)
         }
     }
 
     fn element_end(&mut self) -> Token<'a> {
-        if let Some(pos) = memchr::memchr(b'>', self.rest) {
+        if self
+            .rest
+            .get(2)
+            .is_some_and(|&b| !tag::is_name_start_char(b))
+        {
+            return self.tag_err(ErrorKind::InvalidTagStart);
+        }
+        if let Some(pos) = tag::find_tag_end(self.rest) {
             let (span, rest) = self.rest.split_at(pos + 1);
             self.rest = rest;
             Token::ElementEnd(span)
         } else {
-            self.rest_err()
+            self.rest_err(ErrorKind::UnterminatedTag)
         }
     }
 
@@ -187,41 +1550,149 @@ impl<'a> Tokenizer<'a> {
                 b'!' => self.builtin(rest),
                 b'?' => self.proc(rest),
                 b'/' => self.element_end(),
+                _ if self.options.html && !chr.is_ascii_alphabetic() => self.span(1),
                 _ => self.element(),
             }
+        } else if self.options.html && !self.partial {
+            self.span(1)
         } else {
-            self.rest_err()
+            self.rest_err(ErrorKind::UnterminatedTag)
         }
     }
 }
 
+/// Tokens in the order of the input
+///
+/// The input can only be scanned front to back, as markup can't be recognized
+/// from its end: `<b -->` may end a comment like `<!-- <b -->`. So there is no
+/// shortcut to the last token, and [`Iterator::last`] tokenizes the whole input.
 impl<'a> Iterator for Tokenizer<'a> {
     type Item = Token<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(pos) = match self.depth {
-            0 => memchr::memchr2(b'<', b'&', self.rest),
-            _ => memchr::memchr3(b'<', b'&', b']', self.rest),
+        if let Some((token, after)) = self.peeked.take() {
+            self.set_state(after);
+            return token;
+        }
+        let before = self.rest;
+        let token = self.next_token();
+        if let Some(token) = &token {
+            self.assert_partition(before, token);
+            match token {
+                Token::Element(_) => self.element_depth += 1,
+                Token::ElementEnd(_) => self.element_depth = self.element_depth.saturating_sub(1),
+                _ => {}
+            }
+            self.doc_start &= matches!(token, Token::Bom(_));
+        }
+        if token.is_none() && self.options.emit_eof && !self.eof_emitted && !self.partial {
+            self.eof_emitted = true;
+            return Some(Token::Eof(&self.rest[..0]));
+        }
+        token
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // every token but `Eof` consumes at least one byte
+        let eof = usize::from(self.options.emit_eof && !self.eof_emitted && !self.partial);
+        // in partial mode or before a sentinel, the rest may not be tokenized at all
+        let min = usize::from(!self.rest.is_empty() && !self.partial && self.sentinel.is_none());
+        (min + eof, Some(self.rest.len() + eof))
+    }
+}
+
+impl<'a> Tokenizer<'a> {
+    /// Check that `token` covers exactly the bytes between `before` and `rest` (debug builds only)
+    fn assert_partition(&self, before: &'a [u8], token: &Token<'a>) {
+        let bytes = token.as_bytes();
+        debug_assert!(
+            bytes.as_ptr() == before.as_ptr() && bytes.len() == before.len() - self.rest.len(),
+            "{:?} does not continue the partition at offset {}",
+            token,
+            self.original.len() - before.len(),
+        );
+    }
+
+    fn next_token(&mut self) -> Option<Token<'a>> {
+        if self.check_bom {
+            self.check_bom = false;
+            if self.rest.starts_with(BOM) {
+                let (bom, rest) = self.rest.split_at(BOM.len());
+                self.rest = rest;
+                return Some(Token::Bom(bom));
+            }
+        }
+        if let Some(sentinel) = self.sentinel {
+            if self.rest.starts_with(sentinel) {
+                return None;
+            }
+        }
+        if self.subset_start {
+            self.subset_start = false;
+            let (span, rest) = self.rest.split_at(1);
+            self.rest = rest;
+            self.dtd_depth += 1;
+            return Some(Token::SubsetStart(span));
+        }
+        if let Some(name) = self.raw_text.take() {
+            let before = self.rest;
+            let raw = self.scan_raw_until_close_tag_ignore_case(name);
+            if self.partial && self.rest.is_empty() {
+                // the end tag may be in the next chunk
+                self.rest = before;
+                self.raw_text = Some(name);
+                return None;
+            }
+            if !raw.is_empty() {
+                return Some(Token::Span(raw));
+            }
+        }
+        if self.ignore_section && !self.rest.is_empty() {
+            let Some(len) = dtd::ignore_section_len(self.rest) else {
+                return match self.rest_err(ErrorKind::UnterminatedDecl) {
+                    // the section may be closed by more input
+                    Token::Error([]) => None,
+                    token => {
+                        self.ignore_section = false;
+                        Some(token)
+                    }
+                };
+            };
+            self.ignore_section = false;
+            if len > 0 {
+                let (span, rest) = self.rest.split_at(len);
+                self.rest = rest;
+                return Some(Token::Span(span));
+            }
+        }
+        let in_subset = self.dtd_depth > 0 && !self.options.disable_subsets;
+        if let Some(pos) = match (in_subset, self.options.disable_entities) {
+            (false, false) => memchr::memchr2(b'<', b'&', self.rest),
+            (true, false) => memchr::memchr3(b'<', b'&', b']', self.rest),
+            (false, true) => memchr::memchr(b'<', self.rest),
+            (true, true) => memchr::memchr2(b'<', b']', self.rest),
         } {
             if pos > 0 {
-                let (span, rest) = self.rest.split_at(pos);
-                self.rest = rest;
-                Some(Token::Span(span))
+                Some(self.span(pos))
             } else {
                 let first = self.rest[pos];
-                match first {
-                    b'&' => Some(self.entity()),
-                    b'<' => Some(self.structure()),
-                    b']' => Some(self.decl_end()),
+                let token = match first {
+                    b'&' => self.entity(),
+                    b'<' => self.structure(),
+                    b']' => self.decl_end(),
                     _ => unreachable!(),
+                };
+                match token {
+                    // unterminated token in partial mode
+                    Token::Error([]) => None,
+                    token => Some(token),
                 }
             }
         } else {
             let len = self.rest.len();
-            if len > 0 {
-                let (span, rest) = self.rest.split_at(len);
-                self.rest = rest;
-                Some(Token::Span(span))
+            // in partial mode, the span may be continued by more input
+            if len > 0 && !self.partial {
+                Some(self.span(len))
             } else {
                 None
             }
@@ -229,18 +1700,34 @@ impl<'a> Iterator for Tokenizer<'a> {
     }
 }
 
+/// Tokenize all of `bytes` into a `Vec`
+///
+/// This is a shorthand for collecting a [`Tokenizer`] with the default options.
+#[cfg(feature = "alloc")]
+pub fn tokenize(bytes: &[u8]) -> alloc::vec::Vec<Token<'_>> {
+    Tokenizer::new(bytes).collect()
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{Token, Tokenizer};
+    extern crate std;
+
+    use crate::{
+        ContentModel, DecodeError, ErrorKind, Token, TokenKind, Tokenizer, TokenizerOptions,
+        WhitespaceMode,
+    };
+    use std::string::String;
+    use std::vec::Vec;
 
     const XML_SCHEMA: &str = include_str!("../../XMLSchema.xsd");
 
     #[test]
+    #[allow(clippy::while_let_on_iterator)]
     fn test_tokens() {
         let mut t = Tokenizer::new(XML_SCHEMA.as_bytes());
         assert_eq!(
             t.next().unwrap(),
-            Token::PI(b"<?xml version='1.0' encoding='UTF-8'?>")
+            Token::XmlDecl(b"<?xml version='1.0' encoding='UTF-8'?>")
         );
         assert!(matches!(t.next(), Some(Token::Span(_))));
         assert_eq!(
@@ -254,8 +1741,9 @@ mod tests {
         assert!(matches!(t.next(), Some(Token::Span(_))));
         assert_eq!(
             t.next().unwrap(),
-            Token::Decl(b"<!DOCTYPE xs:schema PUBLIC \"-//W3C//DTD XMLSCHEMA 200102//EN\" \"XMLSchema.dtd\" [")
+            Token::Decl(b"<!DOCTYPE xs:schema PUBLIC \"-//W3C//DTD XMLSCHEMA 200102//EN\" \"XMLSchema.dtd\" ")
         );
+        assert_eq!(t.next().unwrap(), Token::SubsetStart(b"["));
         assert!(matches!(t.next(), Some(Token::Span(_))));
         assert!(matches!(t.next(), Some(Token::Comment(_))));
         assert!(matches!(t.next(), Some(Token::Span(_))));
@@ -317,4 +1805,1810 @@ mod tests {
         }
         assert_eq!(count, 4188);
     }
+
+    #[test]
+    fn test_tokens_stats() {
+        let mut t = Tokenizer::new(XML_SCHEMA.as_bytes());
+        // the tokens up to `<xs:annotation>`, see `test_tokens`
+        assert_eq!(t.nth(113), Some(Token::Element(b"<xs:annotation>")));
+        let stats = t.stats();
+        // the DTD has ended, so only content follows; `</xs:annotation>`
+        // and `</xs:schema>` close elements that were opened before
+        assert_eq!(stats.errors, 0);
+        assert_eq!(stats.xml_decls, 0);
+        assert_eq!(stats.boms, 0);
+        assert_eq!(stats.eofs, 0);
+        assert_eq!(stats.decls, 0);
+        assert_eq!(stats.subset_starts, 0);
+        assert_eq!(stats.decl_ends, 0);
+        assert_eq!(stats.cond_opens, 0);
+        assert_eq!(stats.cond_closes, 0);
+        assert_eq!(stats.element_ends, stats.elements + 2);
+        assert_eq!(stats.total(), 4188);
+    }
+
+    #[test]
+    fn test_scan_raw_until_close_tag() {
+        let mut t = Tokenizer::new(b"<script>if (a < b && c) { x = '</p>'; }</script><p/>");
+        assert_eq!(t.next(), Some(Token::Element(b"<script>")));
+        assert_eq!(
+            t.scan_raw_until_close_tag(b"script"),
+            b"if (a < b && c) { x = '</p>'; }"
+        );
+        assert_eq!(t.next(), Some(Token::ElementEnd(b"</script>")));
+        assert_eq!(t.next(), Some(Token::EmptyElement(b"<p/>")));
+        assert_eq!(t.next(), None);
+
+        let mut t = Tokenizer::new(b"<style>a &gt; b</STYLE >");
+        assert_eq!(t.next(), Some(Token::Element(b"<style>")));
+        assert_eq!(t.scan_raw_until_close_tag(b"style"), b"a &gt; b</STYLE >");
+        assert_eq!(t.next(), None);
+
+        let mut t = Tokenizer::new(b"<style>a &gt; b</styles></STYLE >");
+        assert_eq!(t.next(), Some(Token::Element(b"<style>")));
+        assert_eq!(
+            t.scan_raw_until_close_tag_ignore_case(b"style"),
+            b"a &gt; b</styles>"
+        );
+        assert_eq!(t.next(), Some(Token::ElementEnd(b"</STYLE >")));
+        assert_eq!(t.next(), None);
+    }
+
+    #[test]
+    fn test_line_endings_after_xml_decl() {
+        for newline in [&b"\r\n"[..], b"\n"] {
+            let mut input = b"<?xml version=\"1.0\"?>".to_vec();
+            input.extend_from_slice(newline);
+            input.extend_from_slice(b"<r/>");
+            let mut t = Tokenizer::new(&input);
+            assert_eq!(t.next(), Some(Token::XmlDecl(b"<?xml version=\"1.0\"?>")));
+            assert_eq!(t.next(), Some(Token::Span(newline)));
+            assert_eq!(t.next(), Some(Token::EmptyElement(b"<r/>")));
+            assert_eq!(t.next(), None);
+        }
+    }
+
+    #[test]
+    fn test_with_depth_info() {
+        let t = Tokenizer::new(b"<?pi?><a><b>x</b><c/></a>");
+        let depths = t.with_depth_info().map(|(_, d)| d).collect::<Vec<_>>();
+        assert_eq!(depths, [0, 1, 2, 2, 2, 2, 1]);
+
+        let t = Tokenizer::new(b"</x><a>");
+        let depths = t.with_depth_info().collect::<Vec<_>>();
+        assert_eq!(
+            depths,
+            [(Token::ElementEnd(b"</x>"), 0), (Token::Element(b"<a>"), 1)]
+        );
+
+        let mut t = Tokenizer::new(b"<a><b>x</b></a>");
+        assert_eq!(t.next(), Some(Token::Element(b"<a>")));
+        let depths = t.with_depth_info().collect::<Vec<_>>();
+        assert_eq!(
+            depths,
+            [
+                (Token::Element(b"<b>"), 2),
+                (Token::Span(b"x"), 2),
+                (Token::ElementEnd(b"</b>"), 2),
+                (Token::ElementEnd(b"</a>"), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_contains_byte() {
+        assert!(Token::Span(b"Hello {name}!").contains_byte(b'{'));
+        assert!(!Token::Span(b"Hello World!").contains_byte(b'{'));
+        assert!(Token::Element(b"<x>").contains_byte(b'>'));
+    }
+
+    #[test]
+    fn test_next_nonspace() {
+        let mut t = Tokenizer::new(b"<a>\n  <b> x </b>\r\n\t<c/>\n</a>\n");
+        assert_eq!(t.next_nonspace(), Some(Token::Element(b"<a>")));
+        assert_eq!(t.next_nonspace(), Some(Token::Element(b"<b>")));
+        assert_eq!(t.next_nonspace(), Some(Token::Span(b" x ")));
+        assert_eq!(t.next_nonspace(), Some(Token::ElementEnd(b"</b>")));
+        assert_eq!(t.next_nonspace(), Some(Token::EmptyElement(b"<c/>")));
+        assert_eq!(t.next_nonspace(), Some(Token::ElementEnd(b"</a>")));
+        assert_eq!(t.next_nonspace(), None);
+    }
+
+    #[test]
+    fn test_minimal_comments() {
+        let tokens = Tokenizer::new(b"<!-->").collect::<Vec<_>>();
+        assert_eq!(tokens, [Token::Error(b"<!-->")]);
+        let tokens = Tokenizer::new(b"<!--->").collect::<Vec<_>>();
+        assert_eq!(tokens, [Token::Error(b"<!--->")]);
+        let tokens = Tokenizer::new(b"<!---->").collect::<Vec<_>>();
+        assert_eq!(tokens, [Token::Comment(b"<!---->")]);
+        let tokens = Tokenizer::new(b"<!-- -->").collect::<Vec<_>>();
+        assert_eq!(tokens, [Token::Comment(b"<!-- -->")]);
+    }
+
+    #[test]
+    fn test_write_decoded_text() {
+        let mut t = Tokenizer::new(b"<p>Tom &amp; Jerry &#x2764;&#65;</p>");
+        assert_eq!(t.next(), Some(Token::Element(b"<p>")));
+        let mut text = String::new();
+        assert_eq!(t.write_decoded_text(&mut text), Ok(()));
+        assert_eq!(text, "Tom & Jerry \u{2764}A");
+        assert_eq!(t.next(), Some(Token::ElementEnd(b"</p>")));
+
+        let mut text = String::new();
+        let mut t = Tokenizer::new(b"a &nbsp; b");
+        assert_eq!(t.write_decoded_text(&mut text), Err(DecodeError::Entity));
+        assert_eq!(text, "a ");
+
+        let mut t = Tokenizer::new(b"\xFF");
+        assert_eq!(t.write_decoded_text(&mut text), Err(DecodeError::Utf8));
+
+        let mut text = String::new();
+        let mut t = Tokenizer::new(b"a & b");
+        assert_eq!(t.write_decoded_text(&mut text), Err(DecodeError::Entity));
+        let mut t = Tokenizer::new(b"a \x01");
+        t.set_reject_control_chars(true);
+        assert_eq!(t.write_decoded_text(&mut text), Err(DecodeError::Malformed));
+    }
+
+    #[test]
+    fn test_write_decoded_text_end() {
+        let mut text = String::new();
+        let mut t = Tokenizer::until_sentinel(b"a &amp; b STOP c", b"STOP");
+        assert_eq!(t.write_decoded_text(&mut text), Ok(()));
+        assert_eq!(text, "a & b ");
+        assert_eq!(t.remaining(), b"STOP c");
+
+        let mut text = String::new();
+        let mut t = Tokenizer::new_partial(b"a &amp; b &am");
+        assert_eq!(t.write_decoded_text(&mut text), Ok(()));
+        assert_eq!(text, "a & b ");
+        assert_eq!(t.remaining(), b"&am");
+
+        let mut text = String::new();
+        let mut t = Tokenizer::new(b"\xEF\xBB\xBFa");
+        assert_eq!(t.write_decoded_text(&mut text), Ok(()));
+        assert_eq!(text, "a");
+    }
+
+    #[test]
+    fn test_write_decoded_surrogate() {
+        let mut text = String::new();
+        let mut t = Tokenizer::new(b"&#x10FFFF;&#xD800;");
+        assert_eq!(t.write_decoded_text(&mut text), Err(DecodeError::Entity));
+        assert_eq!(text, "\u{10FFFF}");
+    }
+
+    #[test]
+    fn test_pi_targets() {
+        let t = Tokenizer::new(
+            b"<?xml-stylesheet href='a.xsl'?><doc><?my-app do it?><x/></doc><?end?>",
+        );
+        let targets = t.pi_targets().collect::<Vec<_>>();
+        assert_eq!(targets, [&b"xml-stylesheet"[..], b"my-app", b"end"]);
+    }
+
+    #[test]
+    fn test_until_sentinel() {
+        let input = b"<a>x</a>\n--END--\n<b/>";
+        let mut t = Tokenizer::until_sentinel(input, b"--END--");
+        assert_eq!(t.next(), Some(Token::Element(b"<a>")));
+        assert_eq!(t.next(), Some(Token::Span(b"x")));
+        assert_eq!(t.next(), Some(Token::ElementEnd(b"</a>")));
+        assert_eq!(t.next(), Some(Token::Span(b"\n")));
+        assert_eq!(t.next(), None);
+        assert_eq!(t.remaining(), b"--END--\n<b/>");
+        assert_eq!(t.next(), None);
+
+        // sentinels are not recognized within markup
+        let input = b"<!-- \n|> --><?pi \n|>?><a>\n|><b/>";
+        let tokens = Tokenizer::until_sentinel(input, b"\n|>").collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                Token::Comment(b"<!-- \n|> -->"),
+                Token::PI(b"<?pi \n|>?>"),
+                Token::Element(b"<a>")
+            ]
+        );
+
+        // sentinels may span a markup boundary
+        let mut t = Tokenizer::until_sentinel(b"a&amp;b\n</frame>", b"\n</frame>");
+        assert_eq!(t.by_ref().count(), 3);
+        assert_eq!(t.remaining(), b"\n</frame>");
+    }
+
+    #[test]
+    fn test_checked_next() {
+        let mut t = Tokenizer::new(b"<a>x&amp;</a><!-- y");
+        assert_eq!(t.checked_next(), Some(Ok(Token::Element(b"<a>"))));
+        assert_eq!(t.checked_next(), Some(Ok(Token::Span(b"x"))));
+        assert_eq!(t.checked_next(), Some(Ok(Token::Entity(b"&amp;"))));
+        assert_eq!(t.checked_next(), Some(Ok(Token::ElementEnd(b"</a>"))));
+        assert_eq!(
+            t.checked_next(),
+            Some(Err((ErrorKind::UnterminatedComment, 13)))
+        );
+        assert_eq!(t.checked_next(), None);
+
+        let cases: [(&[u8], ErrorKind, usize); 6] = [
+            (b"ab<?pi", ErrorKind::UnterminatedPi, 2),
+            (b"a<!DOCTYPE", ErrorKind::UnterminatedDecl, 1),
+            (b"<!DOCTYPE a [ ]", ErrorKind::UnterminatedDecl, 14),
+            (b"a b &amp", ErrorKind::UnterminatedEntity, 4),
+            (b"<a><b", ErrorKind::UnterminatedTag, 3),
+            (b"<a></b", ErrorKind::UnterminatedTag, 3),
+        ];
+        for (input, kind, offset) in cases {
+            let mut t = Tokenizer::new(input);
+            let result = core::iter::from_fn(|| t.checked_next()).find_map(Result::err);
+            assert_eq!(result, Some((kind, offset)), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_allow_short_close() {
+        let input = b"<!-- x --!><a/>";
+        let tokens = Tokenizer::new(input).collect::<Vec<_>>();
+        assert_eq!(tokens, [Token::Error(b"<!-- x --!><a/>")]);
+        let tokens = Tokenizer::new(b"<!-- x --!> -->").collect::<Vec<_>>();
+        assert_eq!(tokens, [Token::Comment(b"<!-- x --!> -->")]);
+
+        let mut t = Tokenizer::new(input);
+        t.set_allow_short_close(true);
+        let tokens = t.collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [Token::Comment(b"<!-- x --!>"), Token::EmptyElement(b"<a/>")]
+        );
+        let mut t = Tokenizer::new(b"<!-- x -->");
+        t.set_allow_short_close(true);
+        assert_eq!(t.next(), Some(Token::Comment(b"<!-- x -->")));
+    }
+
+    #[test]
+    fn test_quoted_attributes() {
+        let tokens = Tokenizer::new(b"<a title=\"a > b\">text</a>").collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                Token::Element(b"<a title=\"a > b\">"),
+                Token::Span(b"text"),
+                Token::ElementEnd(b"</a>"),
+            ]
+        );
+        let tokens = Tokenizer::new(b"<a x='he said \"hi>\"' y=\"'>'\"/>").collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [Token::EmptyElement(b"<a x='he said \"hi>\"' y=\"'>'\"/>")]
+        );
+        let tokens = Tokenizer::new(b"<a x='>' y=\"z>").collect::<Vec<_>>();
+        assert_eq!(tokens, [Token::Error(b"<a x='>' y=\"z>")]);
+    }
+
+    #[test]
+    fn test_tokens_with_raw_ranges() {
+        let input = b"<a>  x y\n</a>\n\t<b/>";
+        let ranges = Tokenizer::new(input)
+            .tokens_with_raw_ranges()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ranges,
+            [
+                (Token::Element(b"<a>"), 0..3, None),
+                (Token::Span(b"  x y\n"), 3..9, Some(5..8)),
+                (Token::ElementEnd(b"</a>"), 9..13, None),
+                (Token::Span(b"\n\t"), 13..15, Some(15..15)),
+                (Token::EmptyElement(b"<b/>"), 15..19, None),
+            ]
+        );
+        assert_eq!(&input[5..8], b"x y");
+    }
+
+    #[test]
+    fn test_markup_free_input() {
+        let input = "plain text without any markup\n".repeat(200);
+        assert!(input.len() > 4096);
+        let mut t = Tokenizer::new(input.as_bytes());
+        assert_eq!(t.next(), Some(Token::Span(input.as_bytes())));
+        assert_eq!(t.next(), None);
+        assert_eq!(t.remaining(), b"");
+    }
+
+    #[test]
+    fn test_elements_named() {
+        let input = br#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:annotation><xs:documentation>See <a href="x">x</a></xs:documentation></xs:annotation>
+  <xs:element name="x"/>
+  <hfp:hasFacet name="length"/>
+</xs:schema>"#;
+        let elements = Tokenizer::new(input)
+            .elements_named(|name| name.starts_with(b"xs:"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            elements,
+            [
+                Token::Element(br#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">"#),
+                Token::Element(b"<xs:annotation>"),
+                Token::Element(b"<xs:documentation>"),
+                Token::EmptyElement(br#"<xs:element name="x"/>"#),
+            ]
+        );
+        let count = Tokenizer::new(input)
+            .elements_named(|name| name == b"a")
+            .count();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_strict_entity_names() {
+        let input = b"&amp;&foo-bar;&x.y:z;&1abc;& ;&;&#123;&caf\xC3\xA9;";
+        let tokens = Tokenizer::new(input).collect::<Vec<_>>();
+        assert!(tokens.iter().all(|t| matches!(t, Token::Entity(_))));
+
+        let mut t = Tokenizer::new(input);
+        t.set_strict(true);
+        let tokens = core::iter::from_fn(|| t.checked_next()).collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                Ok(Token::Entity(b"&amp;")),
+                Ok(Token::Entity(b"&foo-bar;")),
+                Ok(Token::Entity(b"&x.y:z;")),
+                Err((ErrorKind::InvalidEntityName, 21)),
+                Err((ErrorKind::InvalidEntityName, 27)),
+                Err((ErrorKind::InvalidEntityName, 30)),
+                Ok(Token::Entity(b"&#123;")),
+                Ok(Token::Entity(b"&caf\xC3\xA9;")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dedup_whitespace() {
+        let input = b"<a>\n    <b> x  y </b>\n\n\t<c/></a>";
+        let tokens = Tokenizer::new(input)
+            .dedup_whitespace(WhitespaceMode::Collapse)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                Token::Element(b"<a>"),
+                Token::Span(b" "),
+                Token::Element(b"<b>"),
+                Token::Span(b" x  y "),
+                Token::ElementEnd(b"</b>"),
+                Token::Span(b" "),
+                Token::EmptyElement(b"<c/>"),
+                Token::ElementEnd(b"</a>"),
+            ]
+        );
+        let tokens = Tokenizer::new(input)
+            .dedup_whitespace(WhitespaceMode::Drop)
+            .collect::<Vec<_>>();
+        assert_eq!(tokens.len(), 6);
+        assert_eq!(tokens[1], Token::Element(b"<b>"));
+    }
+
+    #[test]
+    fn test_bom() {
+        let bom = b"\xEF\xBB\xBF";
+        let tokens = Tokenizer::new(bom).collect::<Vec<_>>();
+        assert_eq!(tokens, [Token::Bom(bom)]);
+        assert_eq!(Tokenizer::new_skip_bom(bom).next(), None);
+
+        let input = b"\xEF\xBB\xBF<a/>";
+        let tokens = Tokenizer::new(input).collect::<Vec<_>>();
+        assert_eq!(tokens, [Token::Bom(bom), Token::EmptyElement(b"<a/>")]);
+        let tokens = Tokenizer::new_skip_bom(input).collect::<Vec<_>>();
+        assert_eq!(tokens, [Token::EmptyElement(b"<a/>")]);
+        let tokens = Tokenizer::new_skip_bom(b"<a/>").collect::<Vec<_>>();
+        assert_eq!(tokens, [Token::EmptyElement(b"<a/>")]);
+    }
+
+    #[test]
+    fn test_bom_not_at_start() {
+        let bom = b"\xEF\xBB\xBF";
+        let tokens = Tokenizer::new(b"<?xml version='1.0'?>\xEF\xBB\xBF<a/>").collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                Token::XmlDecl(b"<?xml version='1.0'?>"),
+                Token::Span(bom),
+                Token::EmptyElement(b"<a/>"),
+            ]
+        );
+        let tokens = Tokenizer::new(b"<!-- x --><a>\xEF\xBB\xBF</a>").collect::<Vec<_>>();
+        assert_eq!(tokens[2], Token::Span(bom));
+        let tokens = Tokenizer::new(b"\xEF\xBB\xBF\xEF\xBB\xBF").collect::<Vec<_>>();
+        assert_eq!(tokens, [Token::Bom(bom), Token::Span(bom)]);
+        let tokens = Tokenizer::new_skip_bom(b"\xEF\xBB\xBF\xEF\xBB\xBF").collect::<Vec<_>>();
+        assert_eq!(tokens, [Token::Span(bom)]);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_tokens_vec() {
+        let input = b"<?xml version='1.0'?><a x='1'>b &amp; c<d/></a>";
+        assert_eq!(
+            Tokenizer::new(input).tokens_vec(),
+            Tokenizer::new(input).collect::<Vec<_>>()
+        );
+        assert_eq!(Tokenizer::new(b"").tokens_vec(), []);
+        // small inputs never grow, large ones reserve at most 1024 tokens
+        let tokens = Tokenizer::new(input).tokens_vec();
+        assert!(tokens.capacity() >= input.len());
+        let input = [b'a'; 4096];
+        assert_eq!(Tokenizer::new(&input).tokens_vec().capacity(), 1024);
+    }
+
+    #[test]
+    fn test_strict_reserved_pi_targets() {
+        let input = b"<?xml version='1.0'?><?xml-stylesheet href='a'?><?xml-model?><?xmlfoo?><?XML?><?Xml-x?><?xm?>";
+        let tokens = Tokenizer::new(input).collect::<Vec<_>>();
+        assert!(tokens
+            .iter()
+            .all(|t| matches!(t, Token::PI(_) | Token::XmlDecl(_))));
+
+        let mut t = Tokenizer::new(input);
+        t.set_strict(true);
+        let tokens = t.collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                Token::XmlDecl(b"<?xml version='1.0'?>"),
+                Token::PI(b"<?xml-stylesheet href='a'?>"),
+                Token::PI(b"<?xml-model?>"),
+                Token::Error(b"<?xmlfoo?>"),
+                Token::Error(b"<?XML?>"),
+                Token::Error(b"<?Xml-x?>"),
+                Token::PI(b"<?xm?>"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_emit_eof() {
+        let mut t = Tokenizer::new(b"<a/>");
+        t.set_emit_eof(true);
+        assert_eq!(t.next(), Some(Token::EmptyElement(b"<a/>")));
+        assert_eq!(t.next(), Some(Token::Eof(b"")));
+        assert_eq!(t.next(), None);
+        assert_eq!(t.next(), None);
+
+        let mut t = Tokenizer::new(b"");
+        t.set_emit_eof(true);
+        let tokens = t.collect::<Vec<_>>();
+        assert_eq!(tokens, [Token::Eof(b"")]);
+    }
+
+    #[test]
+    fn test_quoted_bracket_in_doctype() {
+        let mut t = Tokenizer::new(b"<!DOCTYPE x SYSTEM \"a[b\" [ <!ELEMENT x ANY> ]><x/>");
+        assert_eq!(t.next(), Some(Token::Decl(b"<!DOCTYPE x SYSTEM \"a[b\" ")));
+        assert_eq!(t.dtd_depth, 0);
+        assert_eq!(t.next(), Some(Token::SubsetStart(b"[")));
+        assert_eq!(t.dtd_depth, 1);
+        assert_eq!(t.next(), Some(Token::Span(b" ")));
+        assert_eq!(t.next(), Some(Token::Decl(b"<!ELEMENT x ANY>")));
+        assert_eq!(t.dtd_depth, 1);
+        assert_eq!(t.next(), Some(Token::Span(b" ")));
+        assert_eq!(t.next(), Some(Token::DeclEnd(b"]>")));
+        assert_eq!(t.dtd_depth, 0);
+        assert_eq!(t.next(), Some(Token::EmptyElement(b"<x/>")));
+
+        let mut t = Tokenizer::new(b"<!DOCTYPE x SYSTEM 'a[b'><x/>");
+        assert_eq!(t.next(), Some(Token::Decl(b"<!DOCTYPE x SYSTEM 'a[b'>")));
+        assert_eq!(t.dtd_depth, 0);
+        assert_eq!(t.next(), Some(Token::EmptyElement(b"<x/>")));
+    }
+
+    #[test]
+    fn test_quoted_markup_in_decl() {
+        let input = b"<!ENTITY x \"a > b [ c\"><!ATTLIST e x CDATA \"a>b\" y CDATA '\"]>'>";
+        let mut t = Tokenizer::new(input);
+        assert_eq!(t.next(), Some(Token::Decl(b"<!ENTITY x \"a > b [ c\">")));
+        assert_eq!(t.dtd_depth, 0);
+        assert_eq!(
+            t.next(),
+            Some(Token::Decl(b"<!ATTLIST e x CDATA \"a>b\" y CDATA '\"]>'>"))
+        );
+        assert_eq!(t.next(), None);
+    }
+
+    #[test]
+    fn test_find_doctype() {
+        let mut t = Tokenizer::new(
+            b"<?xml version='1.0'?>\n<!-- x -->\n<!DOCTYPE a [<!ENTITY b 'c'>]>\n<a/>",
+        );
+        assert_eq!(t.find_doctype(), Some(Token::Decl(b"<!DOCTYPE a ")));
+        assert_eq!(t.next(), Some(Token::SubsetStart(b"[")));
+        assert_eq!(t.next(), Some(Token::Decl(b"<!ENTITY b 'c'>")));
+
+        let mut t = Tokenizer::new(b"<?xml version='1.0'?>\n<a><!DOCTYPE b></a>");
+        assert_eq!(t.find_doctype(), None);
+        assert_eq!(t.next(), Some(Token::Element(b"<a>")));
+
+        assert_eq!(Tokenizer::new(b"").find_doctype(), None);
+
+        let input = b"<!-- x --><!doctype html><html>";
+        let mut t = Tokenizer::new_html(input);
+        assert_eq!(t.find_doctype(), Some(Token::Decl(b"<!doctype html>")));
+        assert_eq!(t.next(), Some(Token::Element(b"<html>")));
+        let mut t = Tokenizer::new_html(b"<!DocType html><html>");
+        assert_eq!(t.find_doctype(), Some(Token::Decl(b"<!DocType html>")));
+        let mut t = Tokenizer::new(input);
+        assert_eq!(t.find_doctype(), None);
+    }
+
+    #[test]
+    fn test_cond_section() {
+        let mut t = Tokenizer::new(b"<!DOCTYPE x [<![INCLUDE[<!ELEMENT x ANY>]]>]>");
+        assert_eq!(t.next(), Some(Token::Decl(b"<!DOCTYPE x ")));
+        assert_eq!(t.next(), Some(Token::SubsetStart(b"[")));
+        assert_eq!(t.next(), Some(Token::CondOpen(b"<![INCLUDE[")));
+        assert_eq!(t.dtd_depth, 2);
+        assert_eq!(t.next(), Some(Token::Decl(b"<!ELEMENT x ANY>")));
+        assert_eq!(t.next(), Some(Token::CondClose(b"]]>")));
+        assert_eq!(t.dtd_depth, 1);
+        assert_eq!(t.next(), Some(Token::DeclEnd(b"]>")));
+        assert_eq!(t.next(), None);
+    }
+
+    #[test]
+    fn test_ignore_section() {
+        let input = b"<!DOCTYPE x [<![IGNORE[<!ELEMENT x ANY><![INCLUDE[ & ]]>]]><![ INCLUDE [<!ELEMENT x ANY>]]>]><x/>";
+        let mut t = Tokenizer::new(input);
+        assert_eq!(t.next(), Some(Token::Decl(b"<!DOCTYPE x ")));
+        assert_eq!(t.next(), Some(Token::SubsetStart(b"[")));
+        assert_eq!(t.next(), Some(Token::CondOpen(b"<![IGNORE[")));
+        assert_eq!(
+            t.next(),
+            Some(Token::Span(b"<!ELEMENT x ANY><![INCLUDE[ & ]]>"))
+        );
+        assert_eq!(t.next(), Some(Token::CondClose(b"]]>")));
+        assert_eq!(t.next(), Some(Token::CondOpen(b"<![ INCLUDE [")));
+        assert_eq!(t.next(), Some(Token::Decl(b"<!ELEMENT x ANY>")));
+        assert_eq!(t.next(), Some(Token::CondClose(b"]]>")));
+        assert_eq!(t.next(), Some(Token::DeclEnd(b"]>")));
+        assert_eq!(t.next(), Some(Token::EmptyElement(b"<x/>")));
+        assert_eq!(t.next(), None);
+
+        let mut t = Tokenizer::new(b"<![IGNORE[]]>");
+        assert_eq!(t.next(), Some(Token::CondOpen(b"<![IGNORE[")));
+        assert_eq!(t.next(), Some(Token::CondClose(b"]]>")));
+        let mut t = Tokenizer::new(b"<![IGNORE[<!ELEMENT x ANY>");
+        assert_eq!(t.next(), Some(Token::CondOpen(b"<![IGNORE[")));
+        assert_eq!(t.next(), Some(Token::Error(b"<!ELEMENT x ANY>")));
+        assert_eq!(t.next(), None);
+    }
+
+    #[test]
+    fn test_cond_section_parameter_entity() {
+        let mut t = Tokenizer::new(b"<![ %cond; [ <!ELEMENT x EMPTY> ]]>");
+        assert_eq!(t.next(), Some(Token::CondOpen(b"<![ %cond; [")));
+        assert_eq!(t.next(), Some(Token::Span(b" ")));
+        assert_eq!(t.next(), Some(Token::Decl(b"<!ELEMENT x EMPTY>")));
+        assert_eq!(t.next(), Some(Token::Span(b" ")));
+        assert_eq!(t.next(), Some(Token::CondClose(b"]]>")));
+        assert_eq!(t.next(), None);
+        assert_eq!(t.dtd_depth, 0);
+
+        let mut t = Tokenizer::new(b"<![%draft;[");
+        assert_eq!(t.next(), Some(Token::CondOpen(b"<![%draft;[")));
+        let mut t = Tokenizer::new(b"<![ %dr");
+        assert_eq!(t.next(), Some(Token::Error(b"<![ %dr")));
+    }
+
+    #[test]
+    fn test_xml_decl_at_start() {
+        let mut t = Tokenizer::new_skip_bom(b"\xEF\xBB\xBF<?xml version='1.0'?>");
+        assert_eq!(t.next(), Some(Token::XmlDecl(b"<?xml version='1.0'?>")));
+        let mut t = Tokenizer::new(b"<a/><?xml version='1.0'?>");
+        t.next();
+        assert_eq!(t.peek(), Some(Token::PI(b"<?xml version='1.0'?>")));
+        t.scan_to_byte_offset(0);
+        assert_eq!(t.next(), Some(Token::EmptyElement(b"<a/>")));
+        t.scan_to_byte_offset(4);
+        assert_eq!(t.next(), Some(Token::PI(b"<?xml version='1.0'?>")));
+    }
+
+    #[test]
+    fn test_invalid_decl() {
+        for input in [&b"<!x>"[..], b"<!>", b"<!-x-->", b"<!1>"] {
+            let mut t = Tokenizer::new(input);
+            assert_eq!(t.checked_next(), Some(Err((ErrorKind::InvalidDecl, 0))));
+            assert_eq!(t.next(), None);
+        }
+        let tokens = Tokenizer::new(b"<a><!1>b</a>").collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                Token::Element(b"<a>"),
+                Token::Error(b"<!1>"),
+                Token::Span(b"b"),
+                Token::ElementEnd(b"</a>"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_cond_section() {
+        for input in [
+            &b"<![FOO[x]]><a/>"[..],
+            b"<![cdata[x]]><a/>",
+            b"<![ %bad [x]]><a/>",
+        ] {
+            let mut t = Tokenizer::new(input);
+            assert_eq!(
+                t.checked_next(),
+                Some(Err((ErrorKind::InvalidCondSection, 0)))
+            );
+            assert_eq!(t.remaining(), b"<a/>");
+            assert_eq!(t.next(), Some(Token::EmptyElement(b"<a/>")));
+            assert_eq!(t.next(), None);
+        }
+        let mut t = Tokenizer::new(b"<!DOCTYPE x [<![FOO[<!ELEMENT x ANY>]]>]><x/>");
+        assert_eq!(t.nth(1), Some(Token::SubsetStart(b"[")));
+        assert_eq!(t.next(), Some(Token::Error(b"<![FOO[")));
+        assert_eq!(t.next(), Some(Token::Decl(b"<!ELEMENT x ANY>")));
+    }
+
+    #[test]
+    fn test_partition() {
+        let inputs: [&[u8]; 8] = [
+            b"\xEF\xBB\xBF<?xml version='1.0'?><a x='>'>text &amp; more</a>",
+            b"<!DOCTYPE x [<!ENTITY y 'z'><![ %c; [<!ELEMENT x ANY>]]>]><x/>",
+            b"<!-- comment --><?pi data?>",
+            b"<a",
+            b"<!-- unterminated",
+            b"&amp",
+            b"<!DOCTYPE",
+            b"plain text",
+        ];
+        for input in inputs {
+            let mut t = Tokenizer::new(input);
+            t.set_emit_eof(true);
+            let len = t.map(|token| token.as_bytes().len()).sum::<usize>();
+            assert_eq!(len, input.len());
+        }
+    }
+
+    #[test]
+    fn test_new_html() {
+        let input = b"<!doctype html><p class=intro>a < b && c &amp; d<br>\n<script>if (a<b && c) x();</script><style></style><!-- x --!></p>";
+        let tokens = Tokenizer::new_html(input).collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                Token::Decl(b"<!doctype html>"),
+                Token::Element(b"<p class=intro>"),
+                Token::Span(b"a "),
+                Token::Span(b"<"),
+                Token::Span(b" b "),
+                Token::Span(b"&"),
+                Token::Span(b"&"),
+                Token::Span(b" c "),
+                Token::Entity(b"&amp;"),
+                Token::Span(b" d"),
+                Token::Element(b"<br>"),
+                Token::Span(b"\n"),
+                Token::Element(b"<script>"),
+                Token::Span(b"if (a<b && c) x();"),
+                Token::ElementEnd(b"</script>"),
+                Token::Element(b"<style>"),
+                Token::ElementEnd(b"</style>"),
+                Token::Comment(b"<!-- x --!>"),
+                Token::ElementEnd(b"</p>"),
+            ]
+        );
+        let tokens = Tokenizer::new_html(b"a<").collect::<Vec<_>>();
+        assert_eq!(tokens, [Token::Span(b"a"), Token::Span(b"<")]);
+    }
+
+    #[test]
+    fn test_html_bogus_comment() {
+        let tokens = Tokenizer::new_html(b"<!><p><!1 x>a<!-x-->").collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                Token::Comment(b"<!>"),
+                Token::Element(b"<p>"),
+                Token::Comment(b"<!1 x>"),
+                Token::Span(b"a"),
+                Token::Comment(b"<!-x-->"),
+            ]
+        );
+        let mut t = Tokenizer::new_html(b"<!1");
+        assert_eq!(
+            t.checked_next(),
+            Some(Err((ErrorKind::UnterminatedComment, 0)))
+        );
+    }
+
+    #[test]
+    fn test_top_level() {
+        let input = b"<?xml version='1.0'?>\n<a><b><c/>text</b><d/></a><!-- end -->";
+        let tokens = Tokenizer::new(input).top_level().collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                Token::XmlDecl(b"<?xml version='1.0'?>"),
+                Token::Span(b"\n"),
+                Token::Element(b"<a>"),
+                Token::ElementEnd(b"</a>"),
+                Token::Comment(b"<!-- end -->"),
+            ]
+        );
+        let tokens = Tokenizer::new(b"<a/><b/>").top_level().collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [Token::EmptyElement(b"<a/>"), Token::EmptyElement(b"<b/>")]
+        );
+
+        let mut t = Tokenizer::new(b"<a><b><c/></b>text</a><!-- end -->");
+        assert_eq!(t.nth(1), Some(Token::Element(b"<b>")));
+        let tokens = t.top_level().collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [Token::ElementEnd(b"</a>"), Token::Comment(b"<!-- end -->"),]
+        );
+    }
+
+    #[test]
+    fn test_multi_line_tags() {
+        let mut t = Tokenizer::new(b"<a b=\"line1\nline2\">x</a>");
+        assert_eq!(t.next(), Some(Token::Element(b"<a b=\"line1\nline2\">")));
+        assert_eq!(t.next(), Some(Token::Span(b"x")));
+
+        let mut t = Tokenizer::new(b"<a b='\t>\t'\n\tc=\"\r\n>\"\n/>");
+        assert_eq!(
+            t.next(),
+            Some(Token::EmptyElement(b"<a b='\t>\t'\n\tc=\"\r\n>\"\n/>"))
+        );
+        assert_eq!(t.next(), None);
+    }
+
+    #[test]
+    fn test_scan_to_byte_offset() {
+        let input = b"<!DOCTYPE a [<!ENTITY b 'c'>]><a>x &b; y</a>";
+        let mut t = Tokenizer::new(input);
+        t.scan_to_byte_offset(34);
+        assert_eq!(t.next(), Some(Token::Span(b" ")));
+        assert_eq!(t.next(), Some(Token::Entity(b"&b;")));
+
+        t.scan_to_byte_offset(13);
+        assert_eq!(t.dtd_depth, 1);
+        assert_eq!(t.next(), Some(Token::Decl(b"<!ENTITY b 'c'>")));
+        assert_eq!(t.next(), Some(Token::DeclEnd(b"]>")));
+
+        t.scan_to_byte_offset(input.len());
+        assert_eq!(t.next(), None);
+        t.scan_to_byte_offset(0);
+        assert_eq!(t.next(), Some(Token::Decl(b"<!DOCTYPE a ")));
+        assert_eq!(t.next(), Some(Token::SubsetStart(b"[")));
+    }
+
+    #[test]
+    fn test_next_cancellable() {
+        let count = core::cell::Cell::new(0);
+        let mut t = Tokenizer::new(b"<a><b/>text</a>");
+        let stop = || count.get() >= 2;
+        while let Some(_token) = t.next_cancellable(stop) {
+            count.set(count.get() + 1);
+        }
+        assert_eq!(count.get(), 2);
+        assert_eq!(t.remaining(), b"text</a>");
+        assert_eq!(t.next(), Some(Token::Span(b"text")));
+    }
+
+    #[test]
+    fn test_ignorable_whitespace() {
+        let input = b"<!DOCTYPE a [<!ELEMENT a (b*)><!ELEMENT b (#PCDATA)>]><a>\n  <b> </b>\n</a>";
+        let mut models = Vec::new();
+        let mut stack = Vec::new();
+        let mut ignorable = Vec::new();
+        for token in Tokenizer::new(input) {
+            match token {
+                Token::Decl(decl) => models.extend(crate::element_decl(decl)),
+                Token::Element(span) => stack.push(crate::tag::element_name(span).unwrap()),
+                Token::ElementEnd(_) => {
+                    stack.pop();
+                }
+                Token::Span(_) => {
+                    let parent = stack.last().copied();
+                    let context = models.iter().find(|(name, _)| Some(*name) == parent);
+                    ignorable.push(token.is_ignorable_whitespace(context.map(|&(_, m)| m)));
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(ignorable, [true, false, true]);
+
+        assert!(Token::Span(b"\n  ").is_ignorable_whitespace(None));
+        assert!(!Token::Span(b" x ").is_ignorable_whitespace(None));
+        assert!(!Token::Entity(b"&#32;").is_ignorable_whitespace(None));
+        assert!(Token::Span(b" ").is_ignorable_whitespace(Some(ContentModel::Children)));
+        assert!(!Token::Span(b" ").is_ignorable_whitespace(Some(ContentModel::Any)));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_collect_errors() {
+        let mut t = Tokenizer::new(b"<a>&x y;&1;</a><?xmlfoo?><!-- x");
+        t.set_strict(true);
+        assert_eq!(
+            t.collect_errors(),
+            [
+                (3, &b"&x y;"[..]),
+                (8, b"&1;"),
+                (15, b"<?xmlfoo?>"),
+                (25, b"<!-- x"),
+            ]
+        );
+        assert_eq!(Tokenizer::new(b"<a>ok</a>").collect_errors(), []);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_map_names() {
+        use alloc::borrow::Cow;
+
+        let input = b"<xs:schema><xs:element name='xs:a'/><x/></xs:schema >";
+        let mapped = Tokenizer::new(input)
+            .map_names(|name| match name.strip_prefix(b"xs:") {
+                Some(local) => Cow::Owned([&b"xsd:"[..], local].concat()),
+                None => Cow::Borrowed(name),
+            })
+            .collect::<Vec<_>>();
+        let bytes = mapped.iter().map(|(_, b)| &b[..]).collect::<Vec<_>>();
+        assert_eq!(
+            bytes,
+            [
+                &b"<xsd:schema>"[..],
+                b"<xsd:element name='xs:a'/>",
+                b"<x/>",
+                b"</xsd:schema >",
+            ]
+        );
+        assert!(matches!(mapped[0].1, Cow::Owned(_)));
+        assert!(matches!(mapped[2].1, Cow::Borrowed(_)));
+        assert_eq!(mapped[3].0, Token::ElementEnd(b"</xs:schema >"));
+    }
+
+    #[test]
+    fn test_skip_doctype() {
+        let mut t = Tokenizer::new(b"<!DOCTYPE a SYSTEM 'a.dtd'>\n<a/>");
+        t.skip_doctype();
+        assert_eq!(t.next(), Some(Token::Span(b"\n")));
+
+        let mut t = Tokenizer::new(
+            b"\n<!DOCTYPE a [\n<!ENTITY b ']>'>\n<![INCLUDE[<!ELEMENT a ANY>]]>\n]><a/>",
+        );
+        t.skip_doctype();
+        assert_eq!(t.dtd_depth, 0);
+        assert_eq!(t.next(), Some(Token::EmptyElement(b"<a/>")));
+
+        let mut t = Tokenizer::new(b"<?xml version='1.0'?><!DOCTYPE a>");
+        t.skip_doctype();
+        assert_eq!(t.next(), Some(Token::XmlDecl(b"<?xml version='1.0'?>")));
+        t.skip_doctype();
+        assert_eq!(t.next(), None);
+
+        let mut t = Tokenizer::new_html(b"\n<!doctype html>\n<html>");
+        t.skip_doctype();
+        assert_eq!(t.next(), Some(Token::Span(b"\n")));
+        assert_eq!(t.next(), Some(Token::Element(b"<html>")));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_spanned_owned() {
+        use crate::OwnedToken;
+
+        let input = Vec::from(&b"<a>x &amp; y</a>"[..]);
+        let tokens = Tokenizer::new(&input).spanned_owned().collect::<Vec<_>>();
+        drop(input);
+        assert_eq!(
+            tokens,
+            [
+                (0..3, OwnedToken::Element(b"<a>".to_vec())),
+                (3..5, OwnedToken::Span(b"x ".to_vec())),
+                (5..10, OwnedToken::Entity(b"&amp;".to_vec())),
+                (10..12, OwnedToken::Span(b" y".to_vec())),
+                (12..16, OwnedToken::ElementEnd(b"</a>".to_vec())),
+            ]
+        );
+        assert_eq!(tokens[2].1.as_token(), Token::Entity(b"&amp;"));
+    }
+
+    #[test]
+    fn test_reject_control_chars() {
+        let input = b"<a>x\x07y</a>\x01";
+        let tokens = Tokenizer::new(input).collect::<Vec<_>>();
+        assert_eq!(tokens[1], Token::Span(b"x\x07y"));
+
+        let mut t = Tokenizer::new(input);
+        t.set_reject_control_chars(true);
+        assert_eq!(t.next(), Some(Token::Element(b"<a>")));
+        assert_eq!(t.next(), Some(Token::Span(b"x")));
+        assert_eq!(t.checked_next(), Some(Err((ErrorKind::ControlChar, 4))));
+        assert_eq!(t.next(), Some(Token::Span(b"y")));
+        assert_eq!(t.next(), Some(Token::ElementEnd(b"</a>")));
+        assert_eq!(t.next(), Some(Token::Error(b"\x01")));
+        assert_eq!(t.next(), None);
+
+        let mut t = Tokenizer::new(b"<a>\t\n\r x</a>");
+        t.set_reject_control_chars(true);
+        assert_eq!(t.nth(1), Some(Token::Span(b"\t\n\r x")));
+    }
+
+    #[test]
+    fn test_peek_is() {
+        let cases: [(&[u8], TokenKind); 12] = [
+            (b"\xEF\xBB\xBF", TokenKind::Bom),
+            (b"text", TokenKind::Span),
+            (b"&amp;", TokenKind::Entity),
+            (b"&amp", TokenKind::Error),
+            (b"<?pi?>", TokenKind::PI),
+            (b"<!-- x -->", TokenKind::Comment),
+            (b"<!DOCTYPE x>", TokenKind::Decl),
+            (b"<![INCLUDE[", TokenKind::CondOpen),
+            (b"<a>", TokenKind::Element),
+            (b"</a>", TokenKind::ElementEnd),
+            (b"<a", TokenKind::Error),
+            (b"<", TokenKind::Error),
+        ];
+        for (input, kind) in cases {
+            let t = Tokenizer::new(input);
+            assert!(t.peek_is(kind), "{:?}", kind);
+            assert_eq!(t.remaining(), input);
+        }
+
+        let mut t = Tokenizer::new(b"<!DOCTYPE x [<![IGNORE[]]>]>");
+        t.nth(2);
+        assert!(t.peek_is(TokenKind::CondClose));
+        t.next();
+        assert!(t.peek_is(TokenKind::DeclEnd));
+        t.next();
+        assert!(!t.peek_is(TokenKind::Span));
+        assert_eq!(t.peek_kind(), None);
+
+        let mut t = Tokenizer::new(b"ab");
+        t.set_emit_eof(true);
+        t.next();
+        assert!(t.peek_is(TokenKind::Eof));
+    }
+
+    #[test]
+    fn test_cdata() {
+        let tokens = Tokenizer::new(b"<![CDATA[a < b && c]]>").collect::<Vec<_>>();
+        assert_eq!(tokens, [Token::CData(b"<![CDATA[a < b && c]]>")]);
+
+        let mut t = Tokenizer::new(b"<a><![CDATA[]]]]><![CDATA[<b>]]></a>");
+        assert_eq!(t.next(), Some(Token::Element(b"<a>")));
+        assert_eq!(t.next(), Some(Token::CData(b"<![CDATA[]]]]>")));
+        assert_eq!(t.next(), Some(Token::CData(b"<![CDATA[<b>]]>")));
+        assert_eq!(t.next(), Some(Token::ElementEnd(b"</a>")));
+
+        // `]]` and `]>` inside the section don't end it
+        let tokens = Tokenizer::new(b"<![CDATA[a]]b]>c]]>d").collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [Token::CData(b"<![CDATA[a]]b]>c]]>"), Token::Span(b"d")]
+        );
+
+        let mut t = Tokenizer::new(b"<![CDATA[x]]");
+        assert_eq!(
+            t.checked_next(),
+            Some(Err((ErrorKind::UnterminatedCData, 0)))
+        );
+        assert_eq!(t.next(), None);
+    }
+
+    #[test]
+    fn test_next_spanned() {
+        let input = b"\xEF\xBB\xBF<!DOCTYPE a>x&amp;<a/>";
+        let mut t = Tokenizer::new(input);
+        assert_eq!(t.offset(), 0);
+        assert_eq!(t.next_spanned(), Some((0..3, Token::Bom(b"\xEF\xBB\xBF"))));
+        assert_eq!(
+            t.next_spanned(),
+            Some((3..15, Token::Decl(b"<!DOCTYPE a>")))
+        );
+        assert_eq!(t.next_spanned(), Some((15..16, Token::Span(b"x"))));
+        assert_eq!(t.offset(), 16);
+        assert_eq!(t.next_spanned(), Some((16..21, Token::Entity(b"&amp;"))));
+        assert_eq!(
+            t.next_spanned(),
+            Some((21..25, Token::EmptyElement(b"<a/>")))
+        );
+        assert_eq!(t.next_spanned(), None);
+        assert_eq!(t.offset(), input.len());
+
+        let mut t = Tokenizer::new_skip_bom(input);
+        assert_eq!(t.offset(), 3);
+        assert_eq!(t.next_spanned().unwrap().0, 3..15);
+    }
+
+    #[test]
+    fn test_with_options() {
+        let strict = TokenizerOptions {
+            strict: true,
+            ..TokenizerOptions::default()
+        };
+        let mut t = Tokenizer::new(b"<a>&1; b & c</a>").with_options(strict);
+        assert_eq!(t.next(), Some(Token::Element(b"<a>")));
+        assert_eq!(
+            t.checked_next(),
+            Some(Err((ErrorKind::InvalidEntityName, 3)))
+        );
+
+        let lenient = TokenizerOptions {
+            html: true,
+            ..TokenizerOptions::default()
+        };
+        let mut t = t.with_options(lenient);
+        assert_eq!(t.options(), lenient);
+        assert_eq!(t.next(), Some(Token::Span(b" b ")));
+        assert_eq!(t.next(), Some(Token::Span(b"&")));
+        assert_eq!(t.next(), Some(Token::Span(b" c")));
+        assert_eq!(t.next(), Some(Token::ElementEnd(b"</a>")));
+        assert_eq!(t.next(), None);
+    }
+
+    #[test]
+    fn test_empty_element() {
+        let tokens = Tokenizer::new(b"<a/><b></b>").collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                Token::EmptyElement(b"<a/>"),
+                Token::Element(b"<b>"),
+                Token::ElementEnd(b"</b>"),
+            ]
+        );
+        let tokens = Tokenizer::new(b"<a href=\"/x\"><b x='/>'></b></a>").collect::<Vec<_>>();
+        assert_eq!(tokens[0], Token::Element(b"<a href=\"/x\">"));
+        assert_eq!(tokens[1], Token::Element(b"<b x='/>'>"));
+        assert_eq!(tokens[0].kind(), TokenKind::Element);
+    }
+
+    #[test]
+    fn test_unterminated_pi() {
+        let mut t = Tokenizer::new(b"<?target data");
+        assert_eq!(t.checked_next(), Some(Err((ErrorKind::UnterminatedPi, 0))));
+        assert_eq!(t.next(), None);
+
+        let pi = b"<?target data?>";
+        for len in 2..pi.len() {
+            let mut t = Tokenizer::new(&pi[..len]);
+            assert_eq!(
+                t.checked_next(),
+                Some(Err((ErrorKind::UnterminatedPi, 0))),
+                "{:?}",
+                core::str::from_utf8(&pi[..len])
+            );
+            assert_eq!(t.next(), None);
+        }
+        assert_eq!(Tokenizer::new(pi).next(), Some(Token::PI(pi)));
+    }
+
+    #[test]
+    fn test_quoted_gt_in_tags() {
+        let tokens = Tokenizer::new(b"<a title=\"a > b\" q='he said \"hi\" >'>text</a x='>'>")
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                Token::Element(b"<a title=\"a > b\" q='he said \"hi\" >'>"),
+                Token::Span(b"text"),
+                Token::ElementEnd(b"</a x='>'>"),
+            ]
+        );
+        let mut t = Tokenizer::new(b"</a x='>");
+        assert_eq!(t.checked_next(), Some(Err((ErrorKind::UnterminatedTag, 0))));
+    }
+
+    #[test]
+    fn test_leaf_text() {
+        let leaves = Tokenizer::new(b"<r><a>1</a><b>2</b></r>")
+            .leaf_text()
+            .collect::<Vec<_>>();
+        assert_eq!(leaves, [(&b"a"[..], &b"1"[..]), (b"b", b"2")]);
+
+        let input = b"<r><a>x &amp; <![CDATA[y]]></a><b><c/></b><d><!-- x --></d><e></e></r>";
+        let leaves = Tokenizer::new(input).leaf_text().collect::<Vec<_>>();
+        assert_eq!(
+            leaves,
+            [(&b"a"[..], &b"x &amp; <![CDATA[y]]>"[..]), (b"e", b"")]
+        );
+    }
+
+    #[test]
+    fn test_element_depth() {
+        let mut t = Tokenizer::new(b"</x><!DOCTYPE a [<!ELEMENT a ANY>]><a><b/><c>x</c></a></a>");
+        let mut depths = Vec::new();
+        while t.next().is_some() {
+            depths.push(t.depth());
+        }
+        assert_eq!(depths, [0, 0, 0, 0, 0, 1, 1, 2, 2, 1, 0, 0]);
+    }
+
+    #[test]
+    fn test_recover() {
+        let input = b"<a><b x='></a><c/>";
+        let tokens = Tokenizer::new(input).collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [Token::Element(b"<a>"), Token::Error(b"<b x='></a><c/>")]
+        );
+        let mut t = Tokenizer::new(input);
+        t.set_recover(true);
+        let tokens = t.collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                Token::Element(b"<a>"),
+                Token::Error(b"<b x='>"),
+                Token::ElementEnd(b"</a>"),
+                Token::EmptyElement(b"<c/>"),
+            ]
+        );
+
+        let input = b"<a><b x='1></a>&amp <!-- x";
+        let tokens = Tokenizer::new(input).collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                Token::Element(b"<a>"),
+                Token::Error(b"<b x='1></a>&amp <!-- x")
+            ]
+        );
+
+        let mut t = Tokenizer::new(input);
+        t.set_recover(true);
+        let tokens = t.collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                Token::Element(b"<a>"),
+                Token::Error(b"<b x='1>"),
+                Token::ElementEnd(b"</a>"),
+                Token::Error(b"&amp"),
+                Token::Span(b" "),
+                Token::Error(b"<!-- x"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unterminated_at_end() {
+        let cases: [(&[u8], ErrorKind); 6] = [
+            (b"&", ErrorKind::UnterminatedEntity),
+            (b"<", ErrorKind::UnterminatedTag),
+            (b"<!", ErrorKind::UnterminatedDecl),
+            (b"<?", ErrorKind::UnterminatedPi),
+            (b"</", ErrorKind::UnterminatedTag),
+            (b"<x", ErrorKind::UnterminatedTag),
+        ];
+        for (end, kind) in cases {
+            let input = [&b"<a>text"[..], end].concat();
+            let mut t = Tokenizer::new(&input);
+            assert_eq!(t.next(), Some(Token::Element(b"<a>")));
+            assert_eq!(t.next(), Some(Token::Span(b"text")));
+            assert_eq!(t.checked_next(), Some(Err((kind, 7))));
+            assert_eq!(t.next(), None);
+
+            let mut t = Tokenizer::new(&input);
+            assert_eq!(t.nth(2), Some(Token::Error(end)));
+        }
+    }
+
+    #[test]
+    fn test_xml_decl() {
+        let tokens = Tokenizer::new(b"<?xml version='1.0'?><?xml-stylesheet?>").collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                Token::XmlDecl(b"<?xml version='1.0'?>"),
+                Token::PI(b"<?xml-stylesheet?>"),
+            ]
+        );
+        let mut t = Tokenizer::new(b"\xEF\xBB\xBF<?xml version='1.0'?>");
+        assert_eq!(t.nth(1), Some(Token::XmlDecl(b"<?xml version='1.0'?>")));
+        let mut t = Tokenizer::new_skip_bom(b"\xEF\xBB\xBF<?xml version='1.0'?>");
+        assert_eq!(t.next(), Some(Token::XmlDecl(b"<?xml version='1.0'?>")));
+
+        assert_eq!(
+            Tokenizer::new(b"<?xmlfoo?>").next(),
+            Some(Token::PI(b"<?xmlfoo?>"))
+        );
+        assert_eq!(
+            Tokenizer::new(b"<?XML?>").next(),
+            Some(Token::PI(b"<?XML?>"))
+        );
+        let mut t = Tokenizer::new(b" <?xml version='1.0'?>");
+        assert_eq!(t.nth(1), Some(Token::PI(b"<?xml version='1.0'?>")));
+    }
+
+    #[test]
+    fn test_token_attributes() {
+        let mut t =
+            Tokenizer::new(b"<input type=\"checkbox\" disabled name = 'a'>text</input><br/>");
+        let attrs = t.next().unwrap().attributes().unwrap().collect::<Vec<_>>();
+        assert_eq!(
+            attrs,
+            [
+                (&b"type"[..], &b"checkbox"[..]),
+                (b"disabled", b""),
+                (b"name", b"a"),
+            ]
+        );
+        assert!(t.next().unwrap().attributes().is_none());
+        assert_eq!(t.next().unwrap().attributes().unwrap().count(), 0);
+        assert_eq!(t.next().unwrap().attributes().unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_token_name() {
+        assert_eq!(
+            Token::Element(b"<ns:tag a=\"1\">").name(),
+            Some(&b"ns:tag"[..])
+        );
+        assert_eq!(
+            Token::ElementEnd(b"</ns:tag >").name(),
+            Some(&b"ns:tag"[..])
+        );
+        assert_eq!(Token::EmptyElement(b"<br/>").name(), Some(&b"br"[..]));
+        assert_eq!(Token::Span(b"<a>").name(), None);
+        assert_eq!(Token::Comment(b"<!-- a -->").name(), None);
+        assert_eq!(Token::PI(b"<?a?>").name(), None);
+    }
+
+    #[test]
+    fn test_token_qname() {
+        assert_eq!(
+            Token::Element(b"<xs:schema>").qname(),
+            Some((Some(&b"xs"[..]), &b"schema"[..]))
+        );
+        assert_eq!(
+            Token::ElementEnd(b"</a:b:c>").qname(),
+            Some((Some(&b"a"[..]), &b"b:c"[..]))
+        );
+        assert_eq!(
+            Token::EmptyElement(b"<:x/>").qname(),
+            Some((None, &b":x"[..]))
+        );
+        assert_eq!(
+            Token::EmptyElement(b"<br/>").qname(),
+            Some((None, &b"br"[..]))
+        );
+        assert_eq!(Token::PI(b"<?a:b?>").qname(), None);
+    }
+
+    #[test]
+    fn test_strict_comments() {
+        let input = b"<!-- a -- b --><!-- ok --><!-- c --->";
+        let tokens = Tokenizer::new(input).collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                Token::Comment(b"<!-- a -- b -->"),
+                Token::Comment(b"<!-- ok -->"),
+                Token::Comment(b"<!-- c --->"),
+            ]
+        );
+
+        let mut t = Tokenizer::new(input);
+        t.set_strict(true);
+        assert_eq!(
+            t.checked_next(),
+            Some(Err((ErrorKind::DoubleHyphenInComment, 0)))
+        );
+        assert_eq!(t.next(), Some(Token::Comment(b"<!-- ok -->")));
+        assert_eq!(
+            t.checked_next(),
+            Some(Err((ErrorKind::DoubleHyphenInComment, 26)))
+        );
+        assert_eq!(t.next(), None);
+
+        let mut t = Tokenizer::new(b"<!-- a -- b");
+        t.set_strict(true);
+        assert_eq!(
+            t.checked_next(),
+            Some(Err((ErrorKind::UnterminatedComment, 0)))
+        );
+    }
+
+    #[test]
+    fn test_decode_entity() {
+        let decoded =
+            Tokenizer::new(b"&amp;&lt;&gt;&quot;&apos;&#1234;&#x1F600;&nbsp;&#xFFFFFFFF;&#xD800;")
+                .map(|token| token.decode_entity())
+                .collect::<Vec<_>>();
+        assert_eq!(
+            decoded,
+            [
+                Some('&'),
+                Some('<'),
+                Some('>'),
+                Some('"'),
+                Some('\''),
+                Some('\u{4D2}'),
+                Some('\u{1F600}'),
+                None,
+                None,
+                None,
+            ]
+        );
+        assert_eq!(Token::Span(b"&amp;").decode_entity(), None);
+        assert_eq!(Token::Entity(b"&#0;").decode_entity(), None);
+        assert_eq!(Token::Entity(b"&#x1;").decode_entity(), None);
+    }
+
+    fn feed_chunks(chunks: &[&[u8]], html: bool) -> Vec<(TokenKind, Vec<u8>)> {
+        fn new(buf: &[u8], partial: bool, html: bool) -> Tokenizer<'_> {
+            let tokens = match partial {
+                true => Tokenizer::new_partial(buf),
+                false => Tokenizer::new(buf),
+            };
+            tokens.with_options(TokenizerOptions {
+                allow_short_close: html,
+                html,
+                ..TokenizerOptions::default()
+            })
+        }
+        let mut buf = Vec::new();
+        let mut out = Vec::new();
+        for chunk in chunks {
+            buf.extend_from_slice(chunk);
+            let mut tokens = new(&buf, true, html);
+            out.extend(tokens.by_ref().map(|t| (t.kind(), t.as_bytes().to_vec())));
+            let consumed = buf.len() - tokens.remaining().len();
+            buf.drain(..consumed);
+        }
+        out.extend(new(&buf, false, html).map(|t| (t.kind(), t.as_bytes().to_vec())));
+        out
+    }
+
+    #[test]
+    fn test_new_partial() {
+        let mut tokens = Tokenizer::new_partial(b"<a>text<");
+        assert_eq!(tokens.next(), Some(Token::Element(b"<a>")));
+        assert_eq!(tokens.next(), Some(Token::Span(b"text")));
+        assert_eq!(tokens.next(), None);
+        assert_eq!(tokens.remaining(), b"<");
+
+        let mut tokens = Tokenizer::new_partial(b"<a>text");
+        assert_eq!(tokens.next(), Some(Token::Element(b"<a>")));
+        assert_eq!(tokens.next(), None);
+        assert_eq!(tokens.remaining(), b"text");
+
+        // raw text waits for its end tag
+        let options = Tokenizer::new_html(b"").options();
+        let mut tokens = Tokenizer::new_partial(b"<script>a</s").with_options(options);
+        assert_eq!(tokens.next(), Some(Token::Element(b"<script>")));
+        assert_eq!(tokens.next(), None);
+        assert_eq!(tokens.remaining(), b"a</s");
+
+        for html in [false, true] {
+            let input: &[u8] = match html {
+                false => b"<r a='1'>x &amp; y<!-- c --><![CDATA[d]]><?p q?><e/></r>",
+                true => b"<p class=a>x < y &am z &amp;<br></p><",
+            };
+            let expected = feed_chunks(&[input], html);
+            for i in 0..=input.len() {
+                let (head, tail) = input.split_at(i);
+                assert_eq!(feed_chunks(&[head, tail], html), expected, "split at {}", i);
+            }
+        }
+    }
+
+    #[test]
+    fn test_reset() {
+        let input = b"<a><b>x</b><c/></a>";
+        let mut tokens = Tokenizer::new(input);
+        tokens.set_strict(true);
+        assert_eq!(tokens.by_ref().take(3).count(), 3);
+        assert_eq!(tokens.remaining(), &input[7..]);
+        assert_eq!(tokens.depth(), 2);
+
+        tokens.reset(b"x<!-- a -- b --><y/>");
+        assert_eq!(tokens.depth(), 0);
+        assert_eq!(tokens.remaining(), b"x<!-- a -- b --><y/>");
+        assert_eq!(
+            tokens.collect::<Vec<_>>(),
+            [
+                Token::Span(b"x"),
+                Token::Error(b"<!-- a -- b -->"),
+                Token::EmptyElement(b"<y/>"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_size_hint() {
+        let input = b"<a>text &amp; more</a>";
+        let mut t = Tokenizer::new(input);
+        while t.size_hint() != (0, Some(0)) {
+            assert_eq!(t.size_hint(), (1, Some(t.remaining().len())));
+            assert!(t.clone().count() <= t.remaining().len());
+            t.next();
+        }
+        assert_eq!(t.next(), None);
+
+        let mut t = Tokenizer::new(b"x");
+        t.set_emit_eof(true);
+        assert_eq!(t.size_hint(), (2, Some(2)));
+        t.next();
+        assert_eq!(t.size_hint(), (1, Some(1)));
+        t.next();
+        assert_eq!(t.size_hint(), (0, Some(0)));
+        assert_eq!(Tokenizer::new_partial(b"<a").size_hint(), (0, Some(2)));
+    }
+
+    #[test]
+    fn test_as_bytes() {
+        assert_eq!(Token::Comment(b"<!-- c -->").as_bytes(), b"<!-- c -->");
+        assert_eq!(Token::Eof(b"").as_bytes(), b"");
+
+        let input = b"\xEF\xBB\xBF<?xml version='1.0'?>\n<!DOCTYPE a [<![IGNORE[x]]>]>\n<a b='>'>x &lt; <![CDATA[y]]><?p?><c/><!-- --></a><!-- unterminated";
+        let mut joined = Vec::new();
+        for token in Tokenizer::new(input) {
+            joined.extend_from_slice(token.as_bytes());
+        }
+        assert_eq!(joined, input);
+    }
+
+    #[test]
+    fn test_validate_schema() {
+        assert_eq!(Tokenizer::validate(XML_SCHEMA.as_bytes()), Ok(()));
+    }
+
+    #[test]
+    fn test_is_whitespace() {
+        assert!(Token::Span(b" \n\t\r").is_whitespace());
+        assert!(!Token::Span(b" \n\tx").is_whitespace());
+        assert!(!Token::Comment(b"<!-- -->").is_whitespace());
+        assert!(!Token::Entity(b"&#32;").is_whitespace());
+
+        let tokens = Tokenizer::new(b"<a>\n  <b> x </b>\n</a>\n")
+            .significant()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                Token::Element(b"<a>"),
+                Token::Element(b"<b>"),
+                Token::Span(b" x "),
+                Token::ElementEnd(b"</b>"),
+                Token::ElementEnd(b"</a>"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pi_target_data() {
+        let pi = Token::PI(b"<?xml-stylesheet type=\"text/xsl\" href=\"a.xsl\"?>");
+        assert_eq!(pi.pi_target(), Some(&b"xml-stylesheet"[..]));
+        assert_eq!(pi.pi_data(), Some(&b"type=\"text/xsl\" href=\"a.xsl\""[..]));
+        assert_eq!(Token::PI(b"<?foo?>").pi_target(), Some(&b"foo"[..]));
+        assert_eq!(Token::PI(b"<?foo?>").pi_data(), Some(&b""[..]));
+        assert_eq!(
+            Token::PI(b"<?foo\n\t data ?>").pi_data(),
+            Some(&b"data "[..])
+        );
+        let decl = Token::XmlDecl(b"<?xml version='1.0'?>");
+        assert_eq!(decl.pi_target(), Some(&b"xml"[..]));
+        assert_eq!(decl.pi_data(), Some(&b"version='1.0'"[..]));
+        assert_eq!(Token::Comment(b"<!-- x -->").pi_target(), None);
+        assert_eq!(Token::Element(b"<foo>").pi_data(), None);
+    }
+
+    #[test]
+    fn test_token_doctype() {
+        let mut t = Tokenizer::new(b"<!DOCTYPE a SYSTEM \"a.dtd\" [<!ELEMENT a EMPTY>]><a/>");
+        let doctype = t.next().unwrap().doctype().unwrap();
+        assert_eq!(doctype.name(), b"a");
+        assert_eq!(doctype.system_id(), Some(&b"a.dtd"[..]));
+        assert!(doctype.has_internal_subset());
+        assert_eq!(t.next().unwrap().doctype(), None);
+        assert_eq!(Token::Comment(b"<!DOCTYPE a>").doctype(), None);
+    }
+
+    #[test]
+    fn test_stray_markup_at_end() {
+        let tokens = Tokenizer::new(b"a<").collect::<Vec<_>>();
+        assert_eq!(tokens, [Token::Span(b"a"), Token::Error(b"<")]);
+        let tokens = Tokenizer::new(b"a&").collect::<Vec<_>>();
+        assert_eq!(tokens, [Token::Span(b"a"), Token::Error(b"&")]);
+        let tokens = Tokenizer::new(b"&").collect::<Vec<_>>();
+        assert_eq!(tokens, [Token::Error(b"&")]);
+        let tokens = Tokenizer::new(b"<").collect::<Vec<_>>();
+        assert_eq!(tokens, [Token::Error(b"<")]);
+        assert_eq!(Tokenizer::new_partial(b"a&").nth(1), None);
+    }
+
+    #[test]
+    fn test_peek() {
+        let input = b"<!DOCTYPE a [<!ENTITY b 'c'>]><a>x</a>";
+        let mut t = Tokenizer::new(input);
+        assert_eq!(t.peek(), Some(Token::Decl(b"<!DOCTYPE a ")));
+        assert_eq!(t.peek(), Some(Token::Decl(b"<!DOCTYPE a ")));
+        assert_eq!(t.dtd_depth, 0);
+        assert_eq!(t.remaining(), input);
+        let tokens = t.by_ref().collect::<Vec<_>>();
+        assert_eq!(tokens, Tokenizer::new(input).collect::<Vec<_>>());
+        assert_eq!(t.peek(), None);
+
+        let mut t = Tokenizer::new(input);
+        t.next();
+        assert_eq!(t.peek(), Some(Token::SubsetStart(b"[")));
+        assert_eq!(t.dtd_depth, 0);
+        t.next();
+        assert_eq!(t.peek(), Some(Token::Decl(b"<!ENTITY b 'c'>")));
+        assert_eq!(t.next(), Some(Token::Decl(b"<!ENTITY b 'c'>")));
+        assert_eq!(t.peek(), Some(Token::DeclEnd(b"]>")));
+        assert_eq!(t.dtd_depth, 1);
+        assert_eq!(t.next(), Some(Token::DeclEnd(b"]>")));
+        assert_eq!(t.dtd_depth, 0);
+        assert_eq!(t.peek(), Some(Token::Element(b"<a>")));
+        assert_eq!(t.depth(), 0);
+        t.scan_to_byte_offset(33);
+        assert_eq!(t.peek(), Some(Token::Span(b"x")));
+        assert_eq!(t.next_spanned(), Some((33..34, Token::Span(b"x"))));
+    }
+
+    #[test]
+    fn test_cdata_end_in_text() {
+        let input = b"<a>x]]>y</a>";
+        let tokens = Tokenizer::new(input).collect::<Vec<_>>();
+        assert_eq!(tokens[1], Token::Span(b"x]]>y"));
+
+        let mut t = Tokenizer::new(input);
+        t.set_strict(true);
+        assert_eq!(t.next(), Some(Token::Element(b"<a>")));
+        assert_eq!(t.next(), Some(Token::Span(b"x")));
+        assert_eq!(t.checked_next(), Some(Err((ErrorKind::CDataEndInText, 4))));
+        assert_eq!(t.next(), Some(Token::Span(b"y")));
+        assert_eq!(t.next(), Some(Token::ElementEnd(b"</a>")));
+
+        let mut t = Tokenizer::new(b"<a>]]]></a>");
+        t.set_strict(true);
+        t.next();
+        assert_eq!(t.next(), Some(Token::Span(b"]")));
+        assert_eq!(t.next(), Some(Token::Error(b"]]>")));
+    }
+
+    #[test]
+    fn test_const_new() {
+        const TOKENS: Tokenizer<'static> = Tokenizer::new(b"<a/>");
+        assert_eq!(TOKENS.options(), TokenizerOptions::default());
+        assert_eq!(TOKENS.collect::<Vec<_>>(), [Token::EmptyElement(b"<a/>")]);
+
+        const EMPTY: Tokenizer<'static> = Tokenizer::new(b"");
+        let mut t = EMPTY;
+        assert_eq!(t.next(), None);
+        assert_eq!(t.next(), None);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(Token::Error(b"<").is_error());
+        assert!(!Token::Span(b"<").is_error());
+
+        let mut t = Tokenizer::new(b"<a>&x y;</a><b><?xmlfoo?></b>");
+        t.set_strict(true);
+        assert_eq!(
+            t.errors().collect::<Vec<_>>(),
+            [&b"&x y;"[..], b"<?xmlfoo?>"]
+        );
+        let errors = Tokenizer::new(b"<a><b x='></a>")
+            .errors()
+            .collect::<Vec<_>>();
+        assert_eq!(errors, [&b"<b x='></a>"[..]]);
+    }
+
+    #[test]
+    fn test_normalized_newlines() {
+        let span = Token::Span(b"a\r\nb\rc");
+        assert_eq!(span.normalized_newlines().collect::<Vec<_>>(), b"a\nb\nc");
+        let span = Token::Span(b"\r\r\n\n\r");
+        assert_eq!(span.normalized_newlines().collect::<Vec<_>>(), b"\n\n\n\n");
+        let cdata = Token::CData(b"<![CDATA[\r\n]]>");
+        assert_eq!(
+            cdata.normalized_newlines().collect::<Vec<_>>(),
+            b"<![CDATA[\n]]>"
+        );
+        let element = Token::Element(b"<a\r\nb='1'>");
+        assert_eq!(
+            element.normalized_newlines().collect::<Vec<_>>(),
+            b"<a\r\nb='1'>"
+        );
+    }
+
+    #[test]
+    fn test_duplicate_attributes() {
+        assert!(Token::Element(b"<a x=\"1\" x=\"2\">").has_duplicate_attributes());
+        assert!(Token::EmptyElement(b"<a x='1' y='2' x='3'/>").has_duplicate_attributes());
+        assert!(!Token::Element(b"<a xml:id=\"1\" id=\"2\">").has_duplicate_attributes());
+        assert!(Token::Element(b"<input disabled x='1' disabled>").has_duplicate_attributes());
+        assert!(!Token::Element(b"<input disabled checked>").has_duplicate_attributes());
+        assert!(!Token::Element(b"<a>").has_duplicate_attributes());
+        assert!(!Token::Span(b"x='1' x='1'").has_duplicate_attributes());
+    }
+
+    #[test]
+    fn test_token_entity_decl() {
+        let input = b"<!DOCTYPE a [<!ENTITY gt-ish \"a > b\"><!ENTITY % p 'x'><!ENTITY ext SYSTEM \"e.xml\">]>";
+        let decls = Tokenizer::new(input)
+            .filter_map(|token| token.entity_decl())
+            .collect::<Vec<_>>();
+        assert_eq!(decls.len(), 3);
+        assert_eq!(decls[0].name(), b"gt-ish");
+        assert_eq!(decls[0].value(), Some(&b"a > b"[..]));
+        assert!(decls[1].is_parameter());
+        assert_eq!(decls[1].value(), Some(&b"x"[..]));
+        assert_eq!(decls[2].system_id(), Some(&b"e.xml"[..]));
+        assert_eq!(decls[2].value(), None);
+        assert_eq!(Token::Decl(b"<!ELEMENT a ANY>").entity_decl(), None);
+        assert_eq!(Token::Comment(b"<!ENTITY a 'b'>").entity_decl(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_tokenize() {
+        assert_eq!(
+            crate::tokenize(b"<a>b</a>"),
+            [
+                Token::Element(b"<a>"),
+                Token::Span(b"b"),
+                Token::ElementEnd(b"</a>")
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_tokenize_schema() {
+        // 114 tokens up to `<xs:annotation>` and 4188 after it, see `test_tokens`
+        let tokens = crate::tokenize(XML_SCHEMA.as_bytes());
+        assert_eq!(tokens.len(), 114 + 4188);
+        assert_eq!(tokens[113], Token::Element(b"<xs:annotation>"));
+    }
+
+    #[test]
+    fn test_declared_encoding() {
+        let decl = Token::XmlDecl(b"<?xml version=\"1.0\" encoding='UTF-16'?>");
+        assert_eq!(decl.declared_encoding(), Some(&b"UTF-16"[..]));
+        assert_eq!(decl.declared_version(), Some(&b"1.0"[..]));
+
+        let decl = Token::PI(b"<?xml version = '1.1' encoding = \"ISO-8859-1\" ?>");
+        assert_eq!(decl.declared_encoding(), Some(&b"ISO-8859-1"[..]));
+        assert_eq!(decl.declared_version(), Some(&b"1.1"[..]));
+
+        let decl = Token::XmlDecl(b"<?xml version='1.0'?>");
+        assert_eq!(decl.declared_encoding(), None);
+        let pi = Token::PI(b"<?xml-stylesheet encoding='UTF-8'?>");
+        assert_eq!(pi.declared_encoding(), None);
+        assert_eq!(
+            Token::Element(b"<a encoding='UTF-8'>").declared_encoding(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_bracket_after_subset() {
+        let mut t = Tokenizer::new(b"<!DOCTYPE a [<!ELEMENT a ANY>]><a>x]y]></a>");
+        assert_eq!(t.next(), Some(Token::Decl(b"<!DOCTYPE a ")));
+        assert_eq!(t.next(), Some(Token::SubsetStart(b"[")));
+        assert_eq!(t.next(), Some(Token::Decl(b"<!ELEMENT a ANY>")));
+        assert_eq!(t.dtd_depth, 1);
+        assert_eq!(t.next(), Some(Token::DeclEnd(b"]>")));
+        assert_eq!(t.dtd_depth, 0);
+        assert_eq!(t.next(), Some(Token::Element(b"<a>")));
+        assert_eq!(t.next(), Some(Token::Span(b"x]y]>")));
+        assert_eq!(t.next(), Some(Token::ElementEnd(b"</a>")));
+        assert_eq!(t.next(), None);
+
+        let mut t = Tokenizer::new(b"<!DOCTYPE a [<![INCLUDE[<!ELEMENT a ANY>]]>]>]");
+        assert_eq!(t.nth(5), Some(Token::DeclEnd(b"]>")));
+        assert_eq!(t.dtd_depth, 0);
+        assert_eq!(t.next(), Some(Token::Span(b"]")));
+        assert_eq!(t.next(), None);
+    }
+
+    #[test]
+    fn test_decl_end_saturates() {
+        let mut t = Tokenizer::new(b"]]>]>");
+        assert_eq!(t.decl_end(), Token::CondClose(b"]]>"));
+        assert_eq!(t.dtd_depth, 0);
+        assert_eq!(t.decl_end(), Token::DeclEnd(b"]>"));
+        assert_eq!(t.dtd_depth, 0);
+    }
+
+    #[test]
+    fn test_invalid_tag_start() {
+        let mut t = Tokenizer::new(b"<a><@></a>");
+        assert_eq!(t.next(), Some(Token::Element(b"<a>")));
+        assert_eq!(t.checked_next(), Some(Err((ErrorKind::InvalidTagStart, 3))));
+        assert_eq!(t.offset(), 6);
+        assert_eq!(t.next(), Some(Token::ElementEnd(b"</a>")));
+        assert_eq!(t.next(), None);
+
+        let tokens = Tokenizer::new(b"<a>< b></ a>x</a>").collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                Token::Element(b"<a>"),
+                Token::Error(b"< b>"),
+                Token::Error(b"</ a>"),
+                Token::Span(b"x"),
+                Token::ElementEnd(b"</a>"),
+            ]
+        );
+        let mut t = Tokenizer::new(b"<1");
+        assert_eq!(t.next(), Some(Token::Error(b"<1")));
+        assert_eq!(t.next(), None);
+    }
+
+    #[test]
+    fn test_unterminated_entity() {
+        let tokens = Tokenizer::new(b"<a>a & b</a>").collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                Token::Element(b"<a>"),
+                Token::Span(b"a "),
+                Token::Error(b"&"),
+                Token::Span(b" b"),
+                Token::ElementEnd(b"</a>"),
+            ]
+        );
+        let mut t = Tokenizer::new(b"&amp</a>;");
+        assert_eq!(
+            t.checked_next(),
+            Some(Err((ErrorKind::UnterminatedEntity, 0)))
+        );
+        assert_eq!(t.remaining(), b"</a>;");
+        let mut t = Tokenizer::new(b"&a&b;");
+        assert_eq!(t.next(), Some(Token::Error(b"&a")));
+        assert_eq!(t.next(), Some(Token::Entity(b"&b;")));
+
+        // in partial mode, an unterminated entity at the end may be continued
+        let mut t = Tokenizer::new_partial(b"a &am");
+        assert_eq!(t.next(), Some(Token::Span(b"a ")));
+        assert_eq!(t.next(), None);
+        let mut t = Tokenizer::new_partial(b"&am<b/>");
+        assert_eq!(t.next(), Some(Token::Error(b"&am")));
+    }
+
+    #[test]
+    fn test_invalid_tag_start_partial() {
+        let mut t = Tokenizer::new_partial(b"<a><@");
+        assert_eq!(t.next(), Some(Token::Element(b"<a>")));
+        assert_eq!(t.next(), None);
+        assert_eq!(t.offset(), 3);
+        let mut t = Tokenizer::new_partial(b"<@><a>");
+        assert_eq!(t.next(), Some(Token::Error(b"<@>")));
+        assert_eq!(t.next(), Some(Token::Element(b"<a>")));
+    }
+
+    #[test]
+    fn test_bytes_eq() {
+        assert!(Token::Span(b"hi").bytes_eq("hi"));
+        assert!(Token::Span(b"hi").bytes_eq(b"hi"));
+        assert!(Token::Span(b"hi").bytes_eq(&b"hi"[..]));
+        assert!(!Token::Span(b"hi").bytes_eq("ho"));
+        assert!(!Token::Span(b"hi").bytes_eq(b"h"));
+
+        // the kind is not compared
+        assert!(Token::Span(b"</x>").bytes_eq("</x>"));
+        assert!(Token::ElementEnd(b"</x>").bytes_eq("</x>"));
+        assert_ne!(Token::Span(b"</x>"), Token::ElementEnd(b"</x>"));
+
+        let mut t = Tokenizer::new(b"<x>a</x>");
+        assert!(t.nth(2).is_some_and(|token| token.bytes_eq("</x>")));
+    }
 }
@@ -0,0 +1,93 @@
+//! Tokens that own their bytes
+
+use alloc::vec::Vec;
+
+use crate::Token;
+
+/// A [`Token`] with an owned copy of its bytes
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum OwnedToken {
+    /// See [`Token::Span`]
+    Span(Vec<u8>),
+    /// See [`Token::Entity`]
+    Entity(Vec<u8>),
+    /// See [`Token::Error`]
+    Error(Vec<u8>),
+    /// See [`Token::PI`]
+    PI(Vec<u8>),
+    /// See [`Token::XmlDecl`]
+    XmlDecl(Vec<u8>),
+    /// See [`Token::Comment`]
+    Comment(Vec<u8>),
+    /// See [`Token::CData`]
+    CData(Vec<u8>),
+    /// See [`Token::Decl`]
+    Decl(Vec<u8>),
+    /// See [`Token::SubsetStart`]
+    SubsetStart(Vec<u8>),
+    /// See [`Token::DeclEnd`]
+    DeclEnd(Vec<u8>),
+    /// See [`Token::CondOpen`]
+    CondOpen(Vec<u8>),
+    /// See [`Token::CondClose`]
+    CondClose(Vec<u8>),
+    /// See [`Token::Element`]
+    Element(Vec<u8>),
+    /// See [`Token::EmptyElement`]
+    EmptyElement(Vec<u8>),
+    /// See [`Token::ElementEnd`]
+    ElementEnd(Vec<u8>),
+    /// See [`Token::Bom`]
+    Bom(Vec<u8>),
+    /// See [`Token::Eof`]
+    Eof(Vec<u8>),
+}
+
+impl OwnedToken {
+    /// Borrow this token as a [`Token`]
+    pub fn as_token(&self) -> Token<'_> {
+        match self {
+            OwnedToken::Span(b) => Token::Span(b),
+            OwnedToken::Entity(b) => Token::Entity(b),
+            OwnedToken::Error(b) => Token::Error(b),
+            OwnedToken::PI(b) => Token::PI(b),
+            OwnedToken::XmlDecl(b) => Token::XmlDecl(b),
+            OwnedToken::Comment(b) => Token::Comment(b),
+            OwnedToken::CData(b) => Token::CData(b),
+            OwnedToken::Decl(b) => Token::Decl(b),
+            OwnedToken::SubsetStart(b) => Token::SubsetStart(b),
+            OwnedToken::DeclEnd(b) => Token::DeclEnd(b),
+            OwnedToken::CondOpen(b) => Token::CondOpen(b),
+            OwnedToken::CondClose(b) => Token::CondClose(b),
+            OwnedToken::Element(b) => Token::Element(b),
+            OwnedToken::EmptyElement(b) => Token::EmptyElement(b),
+            OwnedToken::ElementEnd(b) => Token::ElementEnd(b),
+            OwnedToken::Bom(b) => Token::Bom(b),
+            OwnedToken::Eof(b) => Token::Eof(b),
+        }
+    }
+}
+
+impl From<Token<'_>> for OwnedToken {
+    fn from(token: Token<'_>) -> Self {
+        match token {
+            Token::Span(b) => OwnedToken::Span(b.to_vec()),
+            Token::Entity(b) => OwnedToken::Entity(b.to_vec()),
+            Token::Error(b) => OwnedToken::Error(b.to_vec()),
+            Token::PI(b) => OwnedToken::PI(b.to_vec()),
+            Token::XmlDecl(b) => OwnedToken::XmlDecl(b.to_vec()),
+            Token::Comment(b) => OwnedToken::Comment(b.to_vec()),
+            Token::CData(b) => OwnedToken::CData(b.to_vec()),
+            Token::Decl(b) => OwnedToken::Decl(b.to_vec()),
+            Token::SubsetStart(b) => OwnedToken::SubsetStart(b.to_vec()),
+            Token::DeclEnd(b) => OwnedToken::DeclEnd(b.to_vec()),
+            Token::CondOpen(b) => OwnedToken::CondOpen(b.to_vec()),
+            Token::CondClose(b) => OwnedToken::CondClose(b.to_vec()),
+            Token::Element(b) => OwnedToken::Element(b.to_vec()),
+            Token::EmptyElement(b) => OwnedToken::EmptyElement(b.to_vec()),
+            Token::ElementEnd(b) => OwnedToken::ElementEnd(b.to_vec()),
+            Token::Bom(b) => OwnedToken::Bom(b.to_vec()),
+            Token::Eof(b) => OwnedToken::Eof(b.to_vec()),
+        }
+    }
+}
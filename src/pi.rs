@@ -0,0 +1,79 @@
+//! Parsing of processing instructions (i.e. `<?target data?>`)
+
+use crate::tag::{attributes_in, is_space, trim_start};
+
+/// Split a processing instruction into its target and data
+///
+/// The whitespace between target and data is not part of either.
+pub(crate) fn pi_target_data(pi: &[u8]) -> Option<(&[u8], &[u8])> {
+    let inner = pi.strip_prefix(b"<?")?.strip_suffix(b"?>")?;
+    let len = inner
+        .iter()
+        .position(|&b| is_space(b))
+        .unwrap_or(inner.len());
+    if len == 0 {
+        return None;
+    }
+    let (target, data) = inner.split_at(len);
+    Some((target, trim_start(data)))
+}
+
+/// Find the value of the pseudo-attribute `name` in the data of a PI
+///
+/// This is how the `version` and `encoding` of an XML declaration are given.
+pub(crate) fn pseudo_attribute<'a>(data: &'a [u8], name: &[u8]) -> Option<&'a [u8]> {
+    attributes_in(data)
+        .find(|&(key, _)| key == name)
+        .map(|(_, value)| value)
+}
+
+/// Targets starting with `xml` that are registered for use
+const REGISTERED_TARGETS: &[&[u8]] = &[b"xml", b"xml-stylesheet", b"xml-model"];
+
+/// Check whether the target of `pi` is reserved for future standardization
+///
+/// Targets that match `xml` case-insensitively are reserved, except for the
+/// XML declaration itself and the registered `xml-stylesheet` and `xml-model`.
+pub(crate) fn has_reserved_target(pi: &[u8]) -> bool {
+    match pi_target_data(pi) {
+        Some((target, _)) => {
+            target.len() >= 3
+                && target[..3].eq_ignore_ascii_case(b"xml")
+                && !REGISTERED_TARGETS.contains(&target)
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pi_target_data, pseudo_attribute};
+
+    #[test]
+    fn test_pi_target_data() {
+        assert_eq!(
+            pi_target_data(b"<?xml-stylesheet type=\"text/xsl\" href=\"a.xsl\"?>"),
+            Some((
+                &b"xml-stylesheet"[..],
+                &b"type=\"text/xsl\" href=\"a.xsl\""[..]
+            ))
+        );
+        assert_eq!(pi_target_data(b"<?foo?>"), Some((&b"foo"[..], &b""[..])));
+        assert_eq!(
+            pi_target_data(b"<?foo \n bar ?>"),
+            Some((&b"foo"[..], &b"bar "[..]))
+        );
+        assert_eq!(pi_target_data(b"<? foo?>"), None);
+        assert_eq!(pi_target_data(b"<?foo"), None);
+    }
+
+    #[test]
+    fn test_pseudo_attribute() {
+        let data = b"version = \"1.0\"\tencoding='UTF-16' standalone='no'";
+        assert_eq!(pseudo_attribute(data, b"version"), Some(&b"1.0"[..]));
+        assert_eq!(pseudo_attribute(data, b"encoding"), Some(&b"UTF-16"[..]));
+        assert_eq!(pseudo_attribute(data, b"standalone"), Some(&b"no"[..]));
+        assert_eq!(pseudo_attribute(b"version='1.0'", b"encoding"), None);
+        assert_eq!(pseudo_attribute(b"", b"version"), None);
+    }
+}
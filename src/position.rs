@@ -0,0 +1,118 @@
+//! Line and column tracking for tokens
+
+use crate::{Token, Tokenizer};
+
+/// A position in a document, with both line and column starting at 1
+///
+/// Columns count characters (not bytes) of UTF-8 input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Iterator pairing tokens with their start [`Position`], see [`Tokenizer::line_col`]
+#[derive(Debug, Clone)]
+pub struct LineCol<'a> {
+    tokens: Tokenizer<'a>,
+    position: Position,
+    tab_width: usize,
+}
+
+impl<'a> LineCol<'a> {
+    pub(crate) fn new(tokens: Tokenizer<'a>) -> Self {
+        Self {
+            tokens,
+            position: Position { line: 1, column: 1 },
+            tab_width: 1,
+        }
+    }
+
+    /// Set the width of tab stops (default 1)
+    ///
+    /// A tab advances the column to the next multiple of `width` (plus one),
+    /// like the tab stops of an editor. A width of `0` is treated as `1`.
+    pub fn with_tab_width(mut self, width: usize) -> Self {
+        self.tab_width = width.max(1);
+        self
+    }
+
+    /// The position after the last token
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    fn advance(&mut self, bytes: &[u8]) {
+        let mut rest = bytes;
+        if let Some(pos) = memchr::memrchr(b'\n', bytes) {
+            self.position.line += memchr::memchr_iter(b'\n', bytes).count();
+            self.position.column = 1;
+            rest = &bytes[(pos + 1)..];
+        }
+        for &b in rest {
+            match b {
+                b'\t' => {
+                    let width = self.tab_width;
+                    self.position.column = ((self.position.column - 1) / width + 1) * width + 1;
+                }
+                // UTF-8 continuation bytes
+                0x80..=0xBF => {}
+                _ => self.position.column += 1,
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for LineCol<'a> {
+    type Item = (Token<'a>, Position);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = self.tokens.next()?;
+        let start = self.position;
+        self.advance(token.as_bytes());
+        Some((token, start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::Position;
+    use crate::{Token, Tokenizer};
+    use std::vec::Vec;
+
+    fn positions(input: &[u8], tab_width: usize) -> Vec<(usize, usize)> {
+        Tokenizer::new(input)
+            .line_col()
+            .with_tab_width(tab_width)
+            .filter(|(token, _)| !matches!(token, Token::Span(_)))
+            .map(|(_, Position { line, column })| (line, column))
+            .collect()
+    }
+
+    #[test]
+    fn test_line_col() {
+        let input = "<a>\n  <b>ä</b><c/>\r\n</a>".as_bytes();
+        assert_eq!(
+            positions(input, 1),
+            [(1, 1), (2, 3), (2, 7), (2, 11), (3, 1)]
+        );
+    }
+
+    #[test]
+    fn test_tab_width() {
+        let input = b"<a>\n\t<b/>\n\t\t<c/>\n  \t<d/>";
+        assert_eq!(positions(input, 1), [(1, 1), (2, 2), (3, 3), (4, 4)]);
+        assert_eq!(positions(input, 4), [(1, 1), (2, 5), (3, 9), (4, 5)]);
+        assert_eq!(positions(input, 8), [(1, 1), (2, 9), (3, 17), (4, 9)]);
+        assert_eq!(positions(input, 0), positions(input, 1));
+    }
+
+    #[test]
+    fn test_position_after_last_token() {
+        let mut t = Tokenizer::new(b"<a>\n\t</a>").line_col().with_tab_width(4);
+        assert_eq!(t.by_ref().count(), 3);
+        assert_eq!(t.position(), Position { line: 2, column: 9 });
+    }
+}
@@ -0,0 +1,128 @@
+//! Scanners that answer simple questions about a document without tokenizing it
+
+fn skip_past<'a>(rest: &'a [u8], needle: &[u8]) -> &'a [u8] {
+    match memchr::memmem::find(rest, needle) {
+        Some(pos) => &rest[(pos + needle.len())..],
+        None => &rest[rest.len()..],
+    }
+}
+
+/// Count the start and empty element tags in `bytes`
+///
+/// This is a fast alternative to counting the `Token::Element`s and
+/// `Token::EmptyElement`s produced by a [`Tokenizer`](crate::Tokenizer): it
+/// only inspects the bytes following each `<`, skipping over end tags, comments,
+/// processing instructions, CDATA sections and declarations. For well-formed
+/// input, both counts agree.
+pub fn count_elements(bytes: &[u8]) -> usize {
+    let mut count = 0;
+    let mut rest = bytes;
+    while let Some(pos) = memchr::memchr(b'<', rest) {
+        rest = &rest[(pos + 1)..];
+        match rest.first() {
+            None => break,
+            Some(b'?') => rest = skip_past(rest, b"?>"),
+            Some(b'!') if rest.starts_with(b"!--") => rest = skip_past(&rest[3..], b"-->"),
+            Some(b'!') if rest.starts_with(b"![CDATA[") => rest = skip_past(rest, b"]]>"),
+            Some(b'!') => match crate::tag::find_decl_end(rest) {
+                Some(pos) => rest = &rest[(pos + 1)..],
+                None => break,
+            },
+            Some(b'/') => {}
+            Some(_) => match crate::tag::find_tag_end(rest) {
+                Some(pos) => {
+                    rest = &rest[(pos + 1)..];
+                    count += 1;
+                }
+                None => break,
+            },
+        }
+    }
+    count
+}
+
+/// Counts of the bytes that start markup, see [`markup_density`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MarkupStats {
+    /// Number of `<` bytes
+    pub less_than: usize,
+    /// Number of `&` bytes
+    pub ampersand: usize,
+    /// Number of `]` bytes
+    pub right_bracket: usize,
+    /// Total number of bytes
+    pub total: usize,
+}
+
+impl MarkupStats {
+    /// The fraction of bytes that are `<`, `&` or `]`, between `0.0` and `1.0`
+    pub fn density(&self) -> f64 {
+        match self.total {
+            0 => 0.0,
+            total => (self.less_than + self.ampersand + self.right_bracket) as f64 / total as f64,
+        }
+    }
+}
+
+/// Count the bytes of `bytes` that may start markup, in a single pass
+pub fn markup_density(bytes: &[u8]) -> MarkupStats {
+    let mut stats = MarkupStats {
+        total: bytes.len(),
+        ..MarkupStats::default()
+    };
+    for pos in memchr::memchr3_iter(b'<', b'&', b']', bytes) {
+        match bytes[pos] {
+            b'<' => stats.less_than += 1,
+            b'&' => stats.ampersand += 1,
+            _ => stats.right_bracket += 1,
+        }
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{count_elements, markup_density, MarkupStats};
+    use crate::{Token, Tokenizer};
+
+    fn token_count(bytes: &[u8]) -> usize {
+        Tokenizer::new(bytes)
+            .filter(|t| matches!(t, Token::Element(_) | Token::EmptyElement(_)))
+            .count()
+    }
+
+    #[test]
+    fn test_count_elements() {
+        let inputs: [&[u8]; 8] = [
+            b"",
+            b"<x>Hello World!</x>",
+            b"<?xml version='1.0'?><!-- <no> --><a><b/><c x='1'>&amp;</c></a>",
+            b"<!DOCTYPE a [<!ELEMENT a EMPTY><!ENTITY x '<b>'>]><a/>",
+            b"<!DOCTYPE a SYSTEM '<b>[' [<!ENTITY x \"<c/>\">]><a/>",
+            b"<?pi <no> ?><a></a><",
+            b"<a><b",
+            b"<a title='<b>'><c x=\"'\"/></a>",
+        ];
+        for input in inputs {
+            assert_eq!(count_elements(input), token_count(input));
+        }
+        assert_eq!(count_elements(b"<a><![CDATA[<no>]]><b/></a>"), 2);
+    }
+
+    #[test]
+    fn test_markup_density() {
+        let stats = markup_density(b"<a>x &amp; y]</a>");
+        assert_eq!(
+            stats,
+            MarkupStats {
+                less_than: 2,
+                ampersand: 1,
+                right_bracket: 1,
+                total: 17,
+            }
+        );
+        assert!((stats.density() - 4.0 / 17.0).abs() < 1e-9);
+        assert_eq!(markup_density(b"").density(), 0.0);
+        assert_eq!(markup_density(b"text").density(), 0.0);
+    }
+}
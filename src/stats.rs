@@ -0,0 +1,121 @@
+//! Counting tokens by kind, see [`Tokenizer::stats`](crate::Tokenizer::stats)
+
+use crate::TokenKind;
+
+/// The number of tokens of each kind, see [`Tokenizer::stats`](crate::Tokenizer::stats)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TokenStats {
+    /// Number of `Span` tokens
+    pub spans: usize,
+    /// Number of `Entity` tokens
+    pub entities: usize,
+    /// Number of `Error` tokens
+    pub errors: usize,
+    /// Number of `PI` tokens
+    pub pis: usize,
+    /// Number of `XmlDecl` tokens
+    pub xml_decls: usize,
+    /// Number of `Comment` tokens
+    pub comments: usize,
+    /// Number of `CData` tokens
+    pub cdata: usize,
+    /// Number of `Decl` tokens
+    pub decls: usize,
+    /// Number of `SubsetStart` tokens
+    pub subset_starts: usize,
+    /// Number of `DeclEnd` tokens
+    pub decl_ends: usize,
+    /// Number of `CondOpen` tokens
+    pub cond_opens: usize,
+    /// Number of `CondClose` tokens
+    pub cond_closes: usize,
+    /// Number of `Element` tokens
+    pub elements: usize,
+    /// Number of `EmptyElement` tokens
+    pub empty_elements: usize,
+    /// Number of `ElementEnd` tokens
+    pub element_ends: usize,
+    /// Number of `Bom` tokens
+    pub boms: usize,
+    /// Number of `Eof` tokens
+    pub eofs: usize,
+}
+
+impl TokenStats {
+    /// The total number of tokens
+    pub fn total(&self) -> usize {
+        self.spans
+            + self.entities
+            + self.errors
+            + self.pis
+            + self.xml_decls
+            + self.comments
+            + self.cdata
+            + self.decls
+            + self.subset_starts
+            + self.decl_ends
+            + self.cond_opens
+            + self.cond_closes
+            + self.elements
+            + self.empty_elements
+            + self.element_ends
+            + self.boms
+            + self.eofs
+    }
+
+    pub(crate) fn add(&mut self, kind: TokenKind) {
+        let count = match kind {
+            TokenKind::Span => &mut self.spans,
+            TokenKind::Entity => &mut self.entities,
+            TokenKind::Error => &mut self.errors,
+            TokenKind::PI => &mut self.pis,
+            TokenKind::XmlDecl => &mut self.xml_decls,
+            TokenKind::Comment => &mut self.comments,
+            TokenKind::CData => &mut self.cdata,
+            TokenKind::Decl => &mut self.decls,
+            TokenKind::SubsetStart => &mut self.subset_starts,
+            TokenKind::DeclEnd => &mut self.decl_ends,
+            TokenKind::CondOpen => &mut self.cond_opens,
+            TokenKind::CondClose => &mut self.cond_closes,
+            TokenKind::Element => &mut self.elements,
+            TokenKind::EmptyElement => &mut self.empty_elements,
+            TokenKind::ElementEnd => &mut self.element_ends,
+            TokenKind::Bom => &mut self.boms,
+            TokenKind::Eof => &mut self.eofs,
+        };
+        *count += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TokenStats;
+    use crate::Tokenizer;
+
+    #[test]
+    fn test_stats() {
+        let input = b"\xEF\xBB\xBF<?xml version='1.0'?><!DOCTYPE a [<!ENTITY b 'c'>]><a>x &amp; <b/><!-- c --><![CDATA[d]]><?p?></a><";
+        let stats = Tokenizer::new(input).stats();
+        assert_eq!(
+            stats,
+            TokenStats {
+                spans: 2,
+                entities: 1,
+                errors: 1,
+                pis: 1,
+                xml_decls: 1,
+                comments: 1,
+                cdata: 1,
+                decls: 2,
+                subset_starts: 1,
+                decl_ends: 1,
+                elements: 1,
+                empty_elements: 1,
+                element_ends: 1,
+                boms: 1,
+                ..TokenStats::default()
+            }
+        );
+        assert_eq!(stats.total(), Tokenizer::new(input).count());
+    }
+}
@@ -0,0 +1,87 @@
+//! Tokens with UTF-8 validated bytes
+
+use crate::Token;
+
+/// A [`Token`] whose bytes are valid UTF-8, see [`Tokenizer::str_tokens`](crate::Tokenizer::str_tokens)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StrToken<'a> {
+    /// See [`Token::Span`]
+    Span(&'a str),
+    /// See [`Token::Entity`]
+    Entity(&'a str),
+    /// See [`Token::Error`]
+    Error(&'a str),
+    /// See [`Token::PI`]
+    PI(&'a str),
+    /// See [`Token::XmlDecl`]
+    XmlDecl(&'a str),
+    /// See [`Token::Comment`]
+    Comment(&'a str),
+    /// See [`Token::CData`]
+    CData(&'a str),
+    /// See [`Token::Decl`]
+    Decl(&'a str),
+    /// See [`Token::SubsetStart`]
+    SubsetStart(&'a str),
+    /// See [`Token::DeclEnd`]
+    DeclEnd(&'a str),
+    /// See [`Token::CondOpen`]
+    CondOpen(&'a str),
+    /// See [`Token::CondClose`]
+    CondClose(&'a str),
+    /// See [`Token::Element`]
+    Element(&'a str),
+    /// See [`Token::EmptyElement`]
+    EmptyElement(&'a str),
+    /// See [`Token::ElementEnd`]
+    ElementEnd(&'a str),
+    /// See [`Token::Bom`]
+    Bom(&'a str),
+    /// See [`Token::Eof`]
+    Eof(&'a str),
+    /// A token of any kind whose bytes are not valid UTF-8
+    Invalid(&'a [u8]),
+}
+
+impl<'a> From<Token<'a>> for StrToken<'a> {
+    fn from(token: Token<'a>) -> Self {
+        let Ok(s) = core::str::from_utf8(token.as_bytes()) else {
+            return StrToken::Invalid(token.as_bytes());
+        };
+        match token {
+            Token::Span(_) => StrToken::Span(s),
+            Token::Entity(_) => StrToken::Entity(s),
+            Token::Error(_) => StrToken::Error(s),
+            Token::PI(_) => StrToken::PI(s),
+            Token::XmlDecl(_) => StrToken::XmlDecl(s),
+            Token::Comment(_) => StrToken::Comment(s),
+            Token::CData(_) => StrToken::CData(s),
+            Token::Decl(_) => StrToken::Decl(s),
+            Token::SubsetStart(_) => StrToken::SubsetStart(s),
+            Token::DeclEnd(_) => StrToken::DeclEnd(s),
+            Token::CondOpen(_) => StrToken::CondOpen(s),
+            Token::CondClose(_) => StrToken::CondClose(s),
+            Token::Element(_) => StrToken::Element(s),
+            Token::EmptyElement(_) => StrToken::EmptyElement(s),
+            Token::ElementEnd(_) => StrToken::ElementEnd(s),
+            Token::Bom(_) => StrToken::Bom(s),
+            Token::Eof(_) => StrToken::Eof(s),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StrToken;
+    use crate::Tokenizer;
+
+    #[test]
+    fn test_str_tokens() {
+        let mut t = Tokenizer::new(b"<a t='\xC3\xA4'>x\xC3<!-- \xE2\x82\xAC --></a>").str_tokens();
+        assert_eq!(t.next(), Some(StrToken::Element("<a t='ä'>")));
+        assert_eq!(t.next(), Some(StrToken::Invalid(b"x\xC3")));
+        assert_eq!(t.next(), Some(StrToken::Comment("<!-- € -->")));
+        assert_eq!(t.next(), Some(StrToken::ElementEnd("</a>")));
+        assert_eq!(t.next(), None);
+    }
+}
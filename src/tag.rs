@@ -0,0 +1,491 @@
+//! Parsing of element tags, i.e. the bytes of `Element` and `ElementEnd` tokens
+
+/// Whitespace as defined by the `S` production of the XML spec
+pub(crate) fn is_space(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\r' | b'\n')
+}
+
+pub(crate) fn trim_start(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|&b| !is_space(b));
+    &bytes[start.unwrap_or(bytes.len())..]
+}
+
+pub(crate) fn trim_end(bytes: &[u8]) -> &[u8] {
+    let end = bytes.iter().rposition(|&b| !is_space(b));
+    &bytes[..end.map_or(0, |end| end + 1)]
+}
+
+pub(crate) fn is_name_start_char(b: u8) -> bool {
+    // any non-ASCII byte is accepted as part of a multi-byte character
+    matches!(b, b':' | b'A'..=b'Z' | b'_' | b'a'..=b'z' | 0x80..)
+}
+
+fn is_name_char(b: u8) -> bool {
+    is_name_start_char(b) || matches!(b, b'-' | b'.' | b'0'..=b'9')
+}
+
+/// Check whether `bytes` match the XML `Name` production
+///
+/// Only the ASCII part of the production is checked exactly.
+pub(crate) fn is_name(bytes: &[u8]) -> bool {
+    match bytes.split_first() {
+        Some((&first, rest)) => is_name_start_char(first) && rest.iter().all(|&b| is_name_char(b)),
+        None => false,
+    }
+}
+
+fn name_len(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .position(|&b| is_space(b) || matches!(b, b'=' | b'/' | b'>'))
+        .unwrap_or(bytes.len())
+}
+
+/// Find the `>` that ends the tag at the start of `bytes`, skipping quoted values
+pub(crate) fn find_tag_end(bytes: &[u8]) -> Option<usize> {
+    // fast path: no quotes before the end of the tag
+    let mut pos = memchr::memchr3(b'>', b'"', b'\'', bytes)?;
+    while bytes[pos] != b'>' {
+        let quote = bytes[pos];
+        pos += 1;
+        pos += memchr::memchr(quote, &bytes[pos..])? + 1;
+        pos += memchr::memchr3(b'>', b'"', b'\'', &bytes[pos..])?;
+    }
+    Some(pos)
+}
+
+/// Find the `>` or `[` that ends the declaration at the start of `bytes`, skipping quoted literals
+pub(crate) fn find_decl_end(bytes: &[u8]) -> Option<usize> {
+    let mut pos = 0;
+    loop {
+        pos += bytes[pos..]
+            .iter()
+            .position(|&b| matches!(b, b'>' | b'[' | b'"' | b'\''))?;
+        match bytes[pos] {
+            quote @ (b'"' | b'\'') => {
+                pos += 1;
+                pos += memchr::memchr(quote, &bytes[pos..])? + 1;
+            }
+            _ => break Some(pos),
+        }
+    }
+}
+
+/// Name of a start tag (`<name ...>`) or end tag (`</name>`)
+pub(crate) fn element_name(tag: &[u8]) -> Option<&[u8]> {
+    let inner = tag.strip_prefix(b"<")?;
+    let inner = inner.strip_prefix(b"/").unwrap_or(inner);
+    let len = name_len(inner);
+    (len > 0).then(|| &inner[..len])
+}
+
+/// Split a qualified name at the first colon into the prefix and the local name
+///
+/// A colon at the start or the end of `name` does not separate a prefix.
+pub(crate) fn split_qname(name: &[u8]) -> (Option<&[u8]>, &[u8]) {
+    match memchr::memchr(b':', name) {
+        Some(pos) if pos > 0 && pos + 1 < name.len() => (Some(&name[..pos]), &name[(pos + 1)..]),
+        _ => (None, name),
+    }
+}
+
+/// The attributes of a start, empty element or end tag
+pub(crate) fn tag_attributes(tag: &[u8]) -> Option<Attributes<'_>> {
+    if matches!(tag.get(1), Some(b'!' | b'?')) {
+        return None;
+    }
+    let name = element_name(tag)?;
+    let start = name.as_ptr() as usize - tag.as_ptr() as usize + name.len();
+    let rest = tag[start..].strip_suffix(b">")?;
+    let rest = rest.strip_suffix(b"/").unwrap_or(rest);
+    Some(Attributes { rest })
+}
+
+/// Attributes in bytes that are not part of a tag, e.g. the data of a PI
+pub(crate) fn attributes_in(rest: &[u8]) -> Attributes<'_> {
+    Attributes { rest }
+}
+
+/// A start or empty element tag, split into its parts
+///
+/// See [`parse_element`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedElement<'a> {
+    name: &'a [u8],
+    self_closing: bool,
+    attrs: &'a [u8],
+}
+
+impl<'a> ParsedElement<'a> {
+    /// The (qualified) name of the element
+    pub fn name(&self) -> &'a [u8] {
+        self.name
+    }
+
+    /// Whether the tag ends in `/>`
+    pub fn is_self_closing(&self) -> bool {
+        self.self_closing
+    }
+
+    /// Iterator over the attributes of the tag
+    pub fn attributes(&self) -> Attributes<'a> {
+        Attributes { rest: self.attrs }
+    }
+
+    /// Iterator over the attributes of the tag, distinguishing attributes without a value
+    ///
+    /// Unlike [`ParsedElement::attributes`], an empty value (`x=""`) is reported
+    /// as `Some(b"")`. An attribute without a value (`disabled`) is reported
+    /// as `None` in [`AttributeMode::Lenient`] and as an error carrying the
+    /// attribute name in [`AttributeMode::Strict`].
+    pub fn attributes_with_mode(&self, mode: AttributeMode) -> CheckedAttributes<'a> {
+        CheckedAttributes {
+            inner: self.attributes(),
+            mode,
+        }
+    }
+
+    pub(crate) fn attributes_raw(&self) -> &'a [u8] {
+        self.attrs
+    }
+
+    /// The attributes of the tag in canonical order
+    ///
+    /// Like in Canonical XML, namespace declarations (`xmlns` and `xmlns:*`)
+    /// come first, sorted by prefix. They are followed by all other attributes,
+    /// which are sorted by their qualified name, as namespaces are not resolved.
+    #[cfg(feature = "alloc")]
+    pub fn sorted_attributes(&self) -> alloc::vec::Vec<(&'a [u8], &'a [u8])> {
+        let mut attrs = alloc::vec::Vec::from_iter(self.attributes());
+        attrs.sort_by_key(|&(name, _)| {
+            let is_namespace = name == b"xmlns" || name.starts_with(b"xmlns:");
+            (!is_namespace, name)
+        });
+        attrs
+    }
+}
+
+/// Parse a standalone start tag (`<a x="1">`) or empty element tag (`<a/>`)
+///
+/// Returns `None` if `tag` is not shaped like `<`, a name, optional attributes,
+/// an optional `/` and `>`. In particular, end tags, comments, declarations and
+/// processing instructions are rejected, as are tags that end before the last
+/// byte, like `<a x>y>`. A `>` in a quoted attribute value does not end the tag.
+pub fn parse_element(tag: &[u8]) -> Option<ParsedElement<'_>> {
+    if find_tag_end(tag)? + 1 != tag.len() {
+        return None;
+    }
+    let inner = tag.strip_prefix(b"<")?.strip_suffix(b">")?;
+    let (inner, self_closing) = match inner.strip_suffix(b"/") {
+        Some(inner) => (inner, true),
+        None => (inner, false),
+    };
+    let len = name_len(inner);
+    if len == 0 || matches!(inner[0], b'!' | b'?') {
+        return None;
+    }
+    let (name, attrs) = inner.split_at(len);
+    if attrs.first().is_some_and(|&b| !is_space(b)) {
+        return None;
+    }
+    Some(ParsedElement {
+        name,
+        self_closing,
+        attrs,
+    })
+}
+
+/// Iterator over the `(name, value)` pairs of attributes in a tag
+///
+/// Values are returned without their surrounding quotes, but otherwise
+/// undecoded. Attributes without a value (e.g. HTML-style `disabled`) yield
+/// an empty value.
+#[derive(Debug, Clone)]
+pub struct Attributes<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> Attributes<'a> {
+    fn next_attribute(&mut self) -> Option<(&'a [u8], Option<&'a [u8]>)> {
+        let mut rest = trim_start(self.rest);
+        loop {
+            match rest.first() {
+                None | Some(b'>') => {
+                    self.rest = &rest[rest.len()..];
+                    return None;
+                }
+                // stray bytes that can't start a name
+                Some(b'/' | b'=') => rest = trim_start(&rest[1..]),
+                Some(_) => break,
+            }
+        }
+        let (name, after) = rest.split_at(name_len(rest));
+        let after = trim_start(after);
+        let Some(after) = after.strip_prefix(b"=") else {
+            self.rest = after;
+            return Some((name, None));
+        };
+        let after = trim_start(after);
+        let (value, rest) = match after.first() {
+            Some(&quote @ (b'"' | b'\'')) => {
+                let inner = &after[1..];
+                match memchr::memchr(quote, inner) {
+                    Some(pos) => (&inner[..pos], &inner[(pos + 1)..]),
+                    None => (inner, &inner[inner.len()..]),
+                }
+            }
+            _ => {
+                let len = after
+                    .iter()
+                    .position(|&b| is_space(b) || b == b'>')
+                    .unwrap_or(after.len());
+                after.split_at(len)
+            }
+        };
+        self.rest = rest;
+        Some((name, Some(value)))
+    }
+}
+
+impl<'a> Iterator for Attributes<'a> {
+    type Item = (&'a [u8], &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (name, value) = self.next_attribute()?;
+        Some((name, value.unwrap_or(&name[name.len()..])))
+    }
+}
+
+/// Whether attributes without a value are accepted, see [`ParsedElement::attributes_with_mode`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeMode {
+    /// Accept HTML-style attributes without a value
+    Lenient,
+    /// Require a value for every attribute, as in XML
+    Strict,
+}
+
+/// Iterator over the attributes in a tag, see [`ParsedElement::attributes_with_mode`]
+#[derive(Debug, Clone)]
+pub struct CheckedAttributes<'a> {
+    inner: Attributes<'a>,
+    mode: AttributeMode,
+}
+
+impl<'a> Iterator for CheckedAttributes<'a> {
+    type Item = Result<(&'a [u8], Option<&'a [u8]>), &'a [u8]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(match self.inner.next_attribute()? {
+            (name, None) if self.mode == AttributeMode::Strict => Err(name),
+            attr => Ok(attr),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::{
+        element_name, find_decl_end, find_tag_end, is_name, parse_element, split_qname,
+        tag_attributes, AttributeMode,
+    };
+    use std::vec::Vec;
+
+    #[test]
+    fn test_find_decl_end() {
+        assert_eq!(find_decl_end(b"DOCTYPE x>"), Some(9));
+        assert_eq!(find_decl_end(b"DOCTYPE x ["), Some(10));
+        assert_eq!(find_decl_end(b"DOCTYPE x SYSTEM 'a[b>' ["), Some(24));
+        assert_eq!(find_decl_end(b"ENTITY x \"a > b [ c\">"), Some(20));
+        assert_eq!(find_decl_end(b"ENTITY x \"a > b"), None);
+    }
+
+    #[test]
+    fn test_is_name() {
+        assert!(is_name(b"amp"));
+        assert!(is_name(b"xs:schema"));
+        assert!(is_name(b"_a-b.c9"));
+        assert!(is_name("café".as_bytes()));
+        assert!(!is_name(b""));
+        assert!(!is_name(b"1abc"));
+        assert!(!is_name(b"-a"));
+        assert!(!is_name(b"a b"));
+    }
+
+    #[test]
+    fn test_find_tag_end() {
+        assert_eq!(find_tag_end(b"<a>"), Some(2));
+        assert_eq!(find_tag_end(b"<a x='>'>"), Some(8));
+        assert_eq!(find_tag_end(b"<a x=\"'>\" y='\"'>>"), Some(15));
+        assert_eq!(find_tag_end(b"<a x='>"), None);
+        assert_eq!(find_tag_end(b"<a x='>'"), None);
+    }
+
+    #[test]
+    fn test_element_name() {
+        assert_eq!(element_name(b"<ns:tag a=\"1\">"), Some(&b"ns:tag"[..]));
+        assert_eq!(element_name(b"</ns:tag >"), Some(&b"ns:tag"[..]));
+        assert_eq!(element_name(b"<br/>"), Some(&b"br"[..]));
+        assert_eq!(element_name(b"< x>"), None);
+    }
+
+    #[test]
+    fn test_split_qname() {
+        assert_eq!(
+            split_qname(b"xs:schema"),
+            (Some(&b"xs"[..]), &b"schema"[..])
+        );
+        assert_eq!(split_qname(b"a:b:c"), (Some(&b"a"[..]), &b"b:c"[..]));
+        assert_eq!(split_qname(b"schema"), (None, &b"schema"[..]));
+        assert_eq!(split_qname(b":x"), (None, &b":x"[..]));
+        assert_eq!(split_qname(b"x:"), (None, &b"x:"[..]));
+    }
+
+    #[test]
+    fn test_non_ascii_names() {
+        assert_eq!(element_name("<café>".as_bytes()), Some("café".as_bytes()));
+        assert_eq!(
+            element_name("</日本語 >".as_bytes()),
+            Some("日本語".as_bytes())
+        );
+
+        let e = parse_element("<ñame ünits=\"3\" 名前='x'/>".as_bytes()).unwrap();
+        assert_eq!(e.name(), "ñame".as_bytes());
+        let attrs = e.attributes().collect::<Vec<_>>();
+        assert_eq!(
+            attrs,
+            [
+                ("ünits".as_bytes(), "3".as_bytes()),
+                ("名前".as_bytes(), "x".as_bytes()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_start_tag() {
+        let e = parse_element(b"<a x=\"1\" y = 'two' z=3 disabled>").unwrap();
+        assert_eq!(e.name(), b"a");
+        assert!(!e.is_self_closing());
+        let attrs = e.attributes().collect::<Vec<_>>();
+        assert_eq!(
+            attrs,
+            [
+                (&b"x"[..], &b"1"[..]),
+                (b"y", b"two"),
+                (b"z", b"3"),
+                (b"disabled", b""),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_empty_tag() {
+        let e = parse_element(b"<xs:any ns='##other'/>").unwrap();
+        assert_eq!(e.name(), b"xs:any");
+        assert!(e.is_self_closing());
+        let attrs = e.attributes().collect::<Vec<_>>();
+        assert_eq!(attrs, [(&b"ns"[..], &b"##other"[..])]);
+
+        let e = parse_element(b"<a href=\"/x\" />").unwrap();
+        assert!(e.is_self_closing());
+        assert_eq!(e.attributes().next(), Some((&b"href"[..], &b"/x"[..])));
+
+        let e = parse_element(b"<br/>").unwrap();
+        assert_eq!(e.name(), b"br");
+        assert!(e.is_self_closing());
+        assert_eq!(e.attributes().next(), None);
+    }
+
+    #[test]
+    fn test_parse_multi_line_tag() {
+        let e = parse_element(b"<a\n\tb=\"line1\nline2\"\r\n\tc='\t'\n/>").unwrap();
+        assert_eq!(e.name(), b"a");
+        assert!(e.is_self_closing());
+        let attrs = e.attributes().collect::<Vec<_>>();
+        assert_eq!(attrs, [(&b"b"[..], &b"line1\nline2"[..]), (b"c", b"\t")]);
+    }
+
+    #[test]
+    fn test_attributes_with_mode() {
+        let e = parse_element(b"<input disabled value=''>").unwrap();
+        let attrs = e
+            .attributes_with_mode(AttributeMode::Lenient)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            attrs,
+            [
+                Ok((&b"disabled"[..], None)),
+                Ok((&b"value"[..], Some(&b""[..])))
+            ]
+        );
+        let attrs = e
+            .attributes_with_mode(AttributeMode::Strict)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            attrs,
+            [Err(&b"disabled"[..]), Ok((&b"value"[..], Some(&b""[..])))]
+        );
+
+        let e = parse_element(b"<a x=\"\">").unwrap();
+        let mut attrs = e.attributes_with_mode(AttributeMode::Strict);
+        assert_eq!(attrs.next(), Some(Ok((&b"x"[..], Some(&b""[..])))));
+        assert_eq!(attrs.next(), None);
+        assert_eq!(e.attributes().next(), Some((&b"x"[..], &b""[..])));
+    }
+
+    #[test]
+    fn test_tag_attributes() {
+        let attrs = tag_attributes(b"<a x = \"1\" y='a/b' disabled/>").unwrap();
+        assert_eq!(
+            attrs.collect::<Vec<_>>(),
+            [(&b"x"[..], &b"1"[..]), (b"y", b"a/b"), (b"disabled", b""),]
+        );
+        assert_eq!(tag_attributes(b"</a>").unwrap().count(), 0);
+        assert_eq!(tag_attributes(b"</a x='1' >").unwrap().count(), 1);
+        assert!(tag_attributes(b"<!-- x -->").is_none());
+    }
+
+    #[test]
+    fn test_parse_non_elements() {
+        assert_eq!(parse_element(b"</a>"), None);
+        assert_eq!(parse_element(b"<!-- a -->"), None);
+        assert_eq!(parse_element(b"<?pi?>"), None);
+        assert_eq!(parse_element(b"<!DOCTYPE x>"), None);
+        assert_eq!(parse_element(b"<a"), None);
+        assert_eq!(parse_element(b"<>"), None);
+        assert_eq!(parse_element(b"text"), None);
+        assert_eq!(parse_element(b"<a x>y>"), None);
+        assert_eq!(parse_element(b"<a x='>"), None);
+        assert_eq!(parse_element(b"<a>>"), None);
+        let e = parse_element(b"<a x='>' y=\"<>\">").unwrap();
+        assert_eq!(
+            e.attributes().collect::<Vec<_>>(),
+            [(&b"x"[..], &b">"[..]), (b"y", b"<>")]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_sorted_attributes() {
+        let e = parse_element(b"<a z=\"1\" a=\"2\">").unwrap();
+        assert_eq!(
+            e.sorted_attributes(),
+            [(&b"a"[..], &b"2"[..]), (b"z", b"1")]
+        );
+
+        let e =
+            parse_element(b"<a b:x='1' xmlns:b='urn:b' a:y='2' xmlns='urn:d' xmlns:a='urn:a'/>")
+                .unwrap();
+        let names = e
+            .sorted_attributes()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [&b"xmlns"[..], b"xmlns:a", b"xmlns:b", b"a:y", b"b:x"]
+        );
+    }
+}
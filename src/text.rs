@@ -0,0 +1,121 @@
+//! Merging runs of text and entities, see [`Tokenizer::text_events`](crate::Tokenizer::text_events)
+
+use core::ops::Range;
+
+use crate::{Token, Tokenizer};
+
+/// A maximal run of consecutive `Span` and `Entity` tokens
+#[derive(Debug, Clone)]
+pub struct TextEvent<'a> {
+    /// The tokenizer before the first token of the run
+    tokens: Tokenizer<'a>,
+    range: Range<usize>,
+}
+
+impl<'a> TextEvent<'a> {
+    /// The byte range of the run in the input
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// The bytes of the whole run, including undecoded entities
+    pub fn as_bytes(&self) -> &'a [u8] {
+        &self.tokens.original[self.range.clone()]
+    }
+
+    /// The `Span` and `Entity` tokens of the run
+    pub fn pieces(&self) -> impl Iterator<Item = Token<'a>> {
+        let mut tokens = self.tokens.clone();
+        let end = self.range.end;
+        core::iter::from_fn(move || match tokens.offset() < end {
+            true => tokens.next(),
+            false => None,
+        })
+    }
+}
+
+/// A [`TextEvent`] or any other token, see [`Tokenizer::text_events`](crate::Tokenizer::text_events)
+#[derive(Debug, Clone)]
+pub enum MergedToken<'a> {
+    /// A run of text and entities
+    Text(TextEvent<'a>),
+    /// Any token but `Span` and `Entity`
+    Token(Token<'a>),
+}
+
+pub(crate) fn next_merged<'a>(tokens: &mut Tokenizer<'a>) -> Option<MergedToken<'a>> {
+    let before = tokens.clone();
+    let start = tokens.offset();
+    match tokens.next()? {
+        Token::Span(_) | Token::Entity(_) => {}
+        token => return Some(MergedToken::Token(token)),
+    }
+    loop {
+        let mut ahead = tokens.clone();
+        match ahead.next() {
+            Some(Token::Span(_) | Token::Entity(_)) => *tokens = ahead,
+            _ => break,
+        }
+    }
+    Some(MergedToken::Text(TextEvent {
+        tokens: before,
+        range: start..tokens.offset(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::MergedToken;
+    use crate::{Token, Tokenizer};
+    use std::vec::Vec;
+
+    #[test]
+    fn test_text_events() {
+        let mut events = Tokenizer::new(b"a&amp;b<x/>c").text_events();
+        let Some(MergedToken::Text(text)) = events.next() else {
+            panic!("expected text");
+        };
+        assert_eq!(text.range(), 0..7);
+        assert_eq!(text.as_bytes(), b"a&amp;b");
+        assert_eq!(
+            text.pieces().collect::<Vec<_>>(),
+            [
+                Token::Span(b"a"),
+                Token::Entity(b"&amp;"),
+                Token::Span(b"b")
+            ]
+        );
+        assert!(matches!(
+            events.next(),
+            Some(MergedToken::Token(Token::EmptyElement(b"<x/>")))
+        ));
+        let Some(MergedToken::Text(text)) = events.next() else {
+            panic!("expected text");
+        };
+        assert_eq!(text.as_bytes(), b"c");
+        assert!(events.next().is_none());
+    }
+
+    #[test]
+    fn test_text_events_structure() {
+        let events = Tokenizer::new(b"<a>&lt;<!-- c -->&gt;x</a>")
+            .text_events()
+            .map(|event| match event {
+                MergedToken::Text(text) => (true, text.as_bytes()),
+                MergedToken::Token(token) => (false, token.as_bytes()),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            [
+                (false, &b"<a>"[..]),
+                (true, b"&lt;"),
+                (false, b"<!-- c -->"),
+                (true, b"&gt;x"),
+                (false, b"</a>"),
+            ]
+        );
+    }
+}
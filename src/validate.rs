@@ -0,0 +1,91 @@
+//! Collecting the malformed tokens of a document
+
+use core::ops::Range;
+
+use crate::{ErrorKind, Tokenizer};
+
+/// The malformed tokens of a document, see [`Tokenizer::validate`]
+///
+/// This stores the first [`ValidationReport::CAPACITY`] errors without
+/// allocating, while [`ValidationReport::count`] counts all of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// Kind, offset and length of each error
+    errors: [(ErrorKind, usize, usize); ValidationReport::CAPACITY],
+    count: usize,
+}
+
+impl ValidationReport {
+    /// The maximum number of errors that are stored
+    pub const CAPACITY: usize = 4;
+
+    /// The total number of errors, including those that were not stored
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// The stored errors, with the byte range of each `Token::Error` in the input
+    pub fn errors(&self) -> impl Iterator<Item = (ErrorKind, Range<usize>)> + '_ {
+        let len = self.count.min(Self::CAPACITY);
+        self.errors[..len]
+            .iter()
+            .map(|&(kind, offset, len)| (kind, offset..(offset + len)))
+    }
+}
+
+pub(crate) fn validate(mut tokens: Tokenizer<'_>) -> Result<(), ValidationReport> {
+    let mut report = ValidationReport {
+        errors: [(ErrorKind::UnterminatedTag, 0, 0); ValidationReport::CAPACITY],
+        count: 0,
+    };
+    while let Some(token) = tokens.checked_next() {
+        if let Err((kind, offset)) = token {
+            if let Some(entry) = report.errors.get_mut(report.count) {
+                *entry = (kind, offset, tokens.offset() - offset);
+            }
+            report.count += 1;
+        }
+    }
+    match report.count {
+        0 => Ok(()),
+        _ => Err(report),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::validate;
+    use crate::{ErrorKind, Tokenizer, ValidationReport};
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(Tokenizer::validate(b"<a x='1'><!-- c --><?p?></a>"), Ok(()));
+
+        let report = Tokenizer::validate(b"<a><b x='</a>").unwrap_err();
+        assert_eq!(report.count(), 1);
+        let mut errors = report.errors();
+        assert_eq!(errors.next(), Some((ErrorKind::UnterminatedTag, 3..13)));
+        assert_eq!(errors.next(), None);
+
+        let report = Tokenizer::validate(b"<?p <!-- c").unwrap_err();
+        assert_eq!(
+            report.errors().next(),
+            Some((ErrorKind::UnterminatedPi, 0..10))
+        );
+        let report = Tokenizer::validate(b"<a/><!-- c").unwrap_err();
+        assert_eq!(
+            report.errors().next(),
+            Some((ErrorKind::UnterminatedComment, 4..10))
+        );
+    }
+
+    #[test]
+    fn test_validate_capacity() {
+        let mut tokens = Tokenizer::new(&[0x01; 10]);
+        tokens.set_reject_control_chars(true);
+        let report = validate(tokens).unwrap_err();
+        assert_eq!(report.count(), 10);
+        assert_eq!(report.errors().count(), ValidationReport::CAPACITY);
+        assert_eq!(report.errors().last(), Some((ErrorKind::ControlChar, 3..4)));
+    }
+}